@@ -1,22 +1,45 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::ffi::OsStr;
 use std::future::Future;
 use std::io::{ErrorKind, SeekFrom};
+use std::net::{Ipv4Addr, SocketAddr};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
 use std::path::{Component, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 
 use axum::extract::{Path, Request, State};
-use axum::http::StatusCode;
+use axum::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::routing::get;
-use axum::{serve, Router};
+use axum::{serve, Json, Router};
 use bytes::Bytes;
+#[cfg(unix)]
+use hyper::server::conn::http1;
+#[cfg(unix)]
+use hyper_util::rt::TokioIo;
+#[cfg(unix)]
+use hyper_util::service::TowerToHyperService;
 use miniz_oxide::deflate::compress_to_vec_zlib;
 use miniz_oxide::inflate::{decompress_to_vec_zlib, DecompressError, TINFLStatus};
+use regex::{Regex, RegexBuilder};
+use reqwest::redirect::Policy;
 use reqwest::{Client, Url};
-use tokio::fs::{read, read_dir, OpenOptions};
+use serde::{Deserialize, Serialize};
+use tokio::fs::{create_dir_all, metadata, read, read_dir, rename, write, File, OpenOptions};
 use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
-use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::net::{TcpListener, TcpSocket};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::{sleep, Duration, Instant};
 use tokio::{io, spawn};
+use tracing::field::Empty;
+use tracing::{info, instrument, warn, Span};
 
 const COMPRESSED_MAGIC: u32 = 0xa1b2c3d4;
 const ZLIB_COMPRESSION_LEVEL: u8 = 6;
@@ -26,6 +49,21 @@ const MANIFEST_CRC_FILE_NAME: &str = "manifest.crc";
 const MANIFEST_FILE_NAME: &str = "manifest.txt";
 const COMPRESSED_MANIFEST_FILE_NAME: &str = "manifest.txt.z";
 const MANIFEST_SUFFIX: &str = "_manifest.txt";
+const PACK_MAGIC: &[u8; 4] = b"PACK";
+const SUPPORTED_PACK_VERSIONS: [u32; 1] = [1];
+const MAX_REDIRECTS: usize = 10;
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+const REVALIDATE_CACHE_CONTROL: &str = "no-cache";
+const NO_STORE_CACHE_CONTROL: &str = "no-store";
+const MAX_TRACKED_REMOTE_ASSETS: usize = 10_000;
+const MAX_CONCURRENT_PACK_CHECKS: usize = 4;
+const STREAMED_PACK_SIZE: u64 = 1024 * 1024 * 1024;
+const FILE_READ_RETRIES: u32 = 2;
+const FILE_READ_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION have no ErrorKind of their own
+#[cfg(windows)]
+const TRANSIENT_WINDOWS_ERRORS: [i32; 2] = [32, 33];
 
 async fn list_files(root_dir: &std::path::Path) -> io::Result<Vec<PathBuf>> {
     let mut files = Vec::new();
@@ -60,6 +98,8 @@ struct Asset {
 struct AssetLocator {
     crc: u32,
     kind: AssetLocatorKind,
+    is_override: bool,
+    is_compressed: bool,
 }
 
 enum AssetLocatorKind {
@@ -72,34 +112,296 @@ struct MemoryAssetLocator {
 }
 
 struct FileAssetLocator {
-    path: PathBuf,
+    // Share the path between all assets in the same pack instead of cloning it for each one
+    path: Arc<PathBuf>,
     data_offset: u64,
     size: u32,
 }
 
 type AssetMap = HashMap<PathBuf, AssetLocator>;
+type PackListing = (PathBuf, Vec<Asset>);
+type CaseInsensitiveNames = HashMap<PathBuf, PathBuf>;
+
+// Keyed by asset name and CRC, so a changed asset never matches an old entry
+struct CompressionCache {
+    capacity: u64,
+    size: u64,
+    next_stamp: u64,
+
+    // Each entry stores the stamp of its last use, so refreshing an entry doesn't need a scan
+    entries: HashMap<(PathBuf, u32), (Bytes, u64)>,
+
+    // Least recently used first
+    order: BTreeMap<u64, (PathBuf, u32)>,
+}
+
+impl CompressionCache {
+    fn new(capacity: u64) -> Self {
+        CompressionCache {
+            capacity,
+            size: 0,
+            next_stamp: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn next_stamp(&mut self) -> u64 {
+        let stamp = self.next_stamp;
+        self.next_stamp += 1;
+        stamp
+    }
+
+    fn get(&mut self, key: &(PathBuf, u32)) -> Option<Bytes> {
+        let new_stamp = self.next_stamp();
+        let (data, stamp) = self.entries.get_mut(key)?;
+        let old_stamp = std::mem::replace(stamp, new_stamp);
+        let data = data.clone();
+
+        let key = self
+            .order
+            .remove(&old_stamp)
+            .expect("Cache entry is missing from the order");
+        self.order.insert(new_stamp, key);
+        Some(data)
+    }
+
+    fn insert(&mut self, key: (PathBuf, u32), data: Bytes) {
+        if data.len() as u64 > self.capacity || self.entries.contains_key(&key) {
+            return;
+        }
+
+        let stamp = self.next_stamp();
+        self.size += data.len() as u64;
+        self.entries.insert(key.clone(), (data, stamp));
+        self.order.insert(stamp, key);
+
+        while self.size > self.capacity {
+            let Some((_, evicted_key)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some((evicted_data, _)) = self.entries.remove(&evicted_key) {
+                self.size -= evicted_data.len() as u64;
+            }
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AssetSource {
+    Memory,
+    File {
+        path: PathBuf,
+        data_offset: u64,
+        size: u32,
+    },
+}
+
+impl From<&AssetLocatorKind> for AssetSource {
+    fn from(kind: &AssetLocatorKind) -> Self {
+        match kind {
+            AssetLocatorKind::Memory(_) => AssetSource::Memory,
+            AssetLocatorKind::File(locator) => AssetSource::File {
+                path: locator.path.to_path_buf(),
+                data_offset: locator.data_offset,
+                size: locator.size,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct AssetMapEntry {
+    name: PathBuf,
+    crc: u32,
+    size: u64,
+    source: AssetSource,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemoteReason {
+    NotFound,
+    CrcMismatch { requested_crc: u32, local_crc: u32 },
+    AlwaysRemote,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum AssetResolution {
+    Rejected {
+        status: u16,
+    },
+    Local {
+        asset_name: PathBuf,
+        crc: u32,
+        compress: bool,
+        source: AssetSource,
+    },
+    Remote {
+        asset_name: PathBuf,
+        reason: RemoteReason,
+    },
+}
+
+#[derive(Serialize)]
+pub struct TinyAsset {
+    name: PathBuf,
+    size: u64,
+    threshold: u64,
+}
+
+#[derive(Serialize)]
+pub struct PackStatus {
+    client_folder: PathBuf,
+    path: PathBuf,
+    parsed: bool,
+    assets: usize,
+    error: Option<String>,
+}
+
+// Timings are cumulative, so pack parsing can exceed the total because packs are parsed in parallel
+#[derive(Default, Serialize)]
+pub struct AssetMapProfile {
+    total_ms: u64,
+    directory_walk_ms: u64,
+    pack_parse_ms: u64,
+    loose_file_ms: u64,
+    remote_manifest_ms: u64,
+    packs: usize,
+    loose_files: usize,
+    assets: usize,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyRedirectPolicy {
+    Follow,
+    SameHost,
+    Disabled,
+}
+
+// Only the asset path limits, the remote asset size limit, the not found message, the
+// always-remote patterns, and name hash stripping are read per request, so changing them affects
+// a running proxy. Everything else is captured when the proxy
+// starts, and the listener settings like the port require binding again.
+#[derive(Clone)]
+pub struct ProxyConfig {
+    pub max_asset_path_length: usize,
+    pub max_asset_path_components: usize,
+    pub redirect_policy: ProxyRedirectPolicy,
+    pub user_agent: String,
+    pub listen_backlog: u32,
+    pub tcp_keepalive: bool,
+    pub debug_endpoints: bool,
+
+    // Remote assets requested with a CRC are stored here and served again without a request
+    pub remote_cache_dir: Option<PathBuf>,
+    pub asset_override_dir: Option<PathBuf>,
+    pub max_remote_asset_size: u64,
+    pub unix_socket_path: Option<PathBuf>,
+    pub case_insensitive_lookup: bool,
+    pub always_remote: Option<Regex>,
+    pub strip_name_hash: bool,
+    pub compression_cache_size: u64,
+    pub strict_remote_manifests: bool,
+    pub max_concurrent_pack_parses: usize,
+
+    // Minimum expected size in bytes by lowercase file extension
+    pub tiny_asset_thresholds: HashMap<String, u64>,
+
+    // Comes from the saved server rather than the settings
+    pub remote_headers: HeaderMap,
+    pub accept_invalid_certs: bool,
+    pub not_found_message: String,
+}
+
+#[derive(Clone)]
+struct ProxyState {
+    http_client: Arc<Client>,
+    asset_map: Arc<AssetMap>,
+    case_insensitive_names: Arc<CaseInsensitiveNames>,
+    game_server_urls: Arc<Vec<Url>>,
+    config: Arc<RwLock<ProxyConfig>>,
+    compression_cache: Arc<Mutex<CompressionCache>>,
+    remote_assets: Arc<Mutex<BTreeSet<String>>>,
+    last_alive: Arc<Mutex<Option<Instant>>>,
+}
+
+fn invalid_pack_data(message: String) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+async fn skip_pack_header(file: &mut File) -> io::Result<()> {
+    // Packs without a header start directly with the first group, so rewind if there's no magic
+    let mut magic = [0; 4];
+    if file.read_exact(&mut magic).await.is_ok() && &magic == PACK_MAGIC {
+        let version = file.read_u32().await?;
+        if !SUPPORTED_PACK_VERSIONS.contains(&version) {
+            return Err(invalid_pack_data(format!(
+                "Unsupported pack version {}",
+                version
+            )));
+        }
+    } else {
+        file.seek(SeekFrom::Start(0)).await?;
+    }
+
+    Ok(())
+}
 
-async fn list_assets_in_pack(pack_path: PathBuf) -> io::Result<(PathBuf, Vec<Asset>)> {
-    let mut file = OpenOptions::new().read(true).open(&pack_path).await?;
+fn normalize_pack_asset_name(name: &str) -> PathBuf {
+    // Pack names may use either separator, but only "/" splits paths on every platform. Split on
+    // both so that in-pack names have the same components as loose files and request paths.
+    name.split(['\\', '/'])
+        .filter(|component| !component.is_empty())
+        .collect()
+}
 
+async fn list_assets_in_pack(pack_path: PathBuf) -> io::Result<PackListing> {
     let mut results = Vec::new();
+    visit_assets_in_pack(&pack_path, |asset| results.push(asset)).await?;
+    Ok((pack_path, results))
+}
+
+async fn visit_assets_in_pack(
+    pack_path: &std::path::Path,
+    mut visit: impl FnMut(Asset),
+) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).open(pack_path).await?;
+    let pack_len = file.metadata().await?.len();
+    skip_pack_header(&mut file).await?;
+
+    let mut skipped_assets = 0;
     loop {
         let next_group_offset = file.read_u32().await? as u64;
         let files_in_group = file.read_u32().await?;
 
         for _ in 0..files_in_group {
             let name_len = file.read_u32().await?;
+            if name_len as u64 > pack_len {
+                return Err(invalid_pack_data(format!(
+                    "Asset name length {} exceeds pack length {}",
+                    name_len, pack_len
+                )));
+            }
+
             let mut name_buffer = vec![0; name_len as usize];
             file.read_exact(&mut name_buffer).await?;
-            let name =
-                PathBuf::from(String::from_utf8(name_buffer).map_err(|_| ErrorKind::InvalidData)?);
 
             let data_offset = file.read_u32().await? as u64;
             let size = file.read_u32().await?;
             let crc = file.read_u32().await?;
 
-            results.push(Asset {
-                name,
+            // Skip a single undecodable name rather than making the whole client unlaunchable
+            let Ok(name) = String::from_utf8(name_buffer) else {
+                skipped_assets += 1;
+                continue;
+            };
+
+            visit(Asset {
+                name: normalize_pack_asset_name(&name),
                 data_offset,
                 size,
                 crc,
@@ -110,10 +412,45 @@ async fn list_assets_in_pack(pack_path: PathBuf) -> io::Result<(PathBuf, Vec<Ass
             break;
         }
 
+        // Groups must move forward through the file, or parsing could loop forever
+        let position = file.stream_position().await?;
+        if next_group_offset <= position || next_group_offset >= pack_len {
+            return Err(invalid_pack_data(format!(
+                "Group offset {} is out of bounds",
+                next_group_offset
+            )));
+        }
+
         file.seek(SeekFrom::Start(next_group_offset)).await?;
     }
 
-    Ok((pack_path, results))
+    if skipped_assets > 0 {
+        warn!(
+            "Skipped {} assets with names that aren't valid UTF-8 in {}",
+            skipped_assets,
+            pack_path.display()
+        );
+    }
+
+    Ok(())
+}
+
+enum PackParse {
+    Listed(JoinHandle<(Duration, io::Result<PackListing>)>),
+    Streamed(PathBuf),
+}
+
+fn insert_pack_asset(asset_map: &mut AssetMap, pack_path: &Arc<PathBuf>, asset: Asset) {
+    asset_map.entry(asset.name).or_insert(AssetLocator {
+        crc: asset.crc,
+        kind: AssetLocatorKind::File(FileAssetLocator {
+            path: Arc::clone(pack_path),
+            data_offset: asset.data_offset,
+            size: asset.size,
+        }),
+        is_override: false,
+        is_compressed: false,
+    });
 }
 
 fn file_name_ends_with(path: &std::path::Path, suffix: &str) -> bool {
@@ -130,7 +467,14 @@ fn file_name_ends_with(path: &std::path::Path, suffix: &str) -> bool {
 }
 
 fn decompress_asset_response(file_data: Vec<u8>) -> Result<Vec<u8>, DecompressError> {
-    if file_data.len() > 8 {
+    if file_data.len() >= 8 {
+        // An empty asset may be sent as the header alone, without a zlib stream
+        let uncompressed_len =
+            u32::from_be_bytes([file_data[4], file_data[5], file_data[6], file_data[7]]);
+        if uncompressed_len == 0 && file_data.len() == 8 {
+            return Ok(Vec::new());
+        }
+
         // Skip the 4-byte magic number and 4-byte length comprising the compressed header
         decompress_to_vec_zlib(&file_data[8..])
     } else {
@@ -141,56 +485,155 @@ fn decompress_asset_response(file_data: Vec<u8>) -> Result<Vec<u8>, DecompressEr
     }
 }
 
-async fn build_asset_map(
+fn loose_file_crc(path: &std::path::Path, file_data: &[u8]) -> (u32, bool) {
+    // Identify pre-compressed files by the CRC of their contents, like every other asset
+    let is_compressed = file_data.len() >= 8 && file_data[..4] == COMPRESSED_MAGIC.to_be_bytes();
+    if is_compressed {
+        match decompress_asset_response(file_data.to_vec()) {
+            Ok(uncompressed_data) => return (crc32fast::hash(&uncompressed_data), true),
+            Err(err) => warn!(
+                "Serving {} as uncompressed because it could not be decompressed: {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+
+    (crc32fast::hash(file_data), false)
+}
+
+fn insert_manifest_crc(
+    asset_map: &mut AssetMap,
+    manifest_path: &std::path::Path,
+    manifest_crc: u32,
+    is_override: bool,
+) {
+    let crc_file_data = manifest_crc.to_string().as_bytes().to_vec();
+    asset_map.insert(
+        manifest_path.with_file_name(MANIFEST_CRC_FILE_NAME),
+        AssetLocator {
+            crc: crc32fast::hash(&crc_file_data),
+            kind: AssetLocatorKind::Memory(MemoryAssetLocator {
+                data: crc_file_data,
+            }),
+            is_override,
+            is_compressed: false,
+        },
+    );
+}
+
+async fn build_layer_asset_map(
     client_folder: &std::path::Path,
-    http_client: &Arc<Client>,
-    game_server_url: &Arc<Url>,
+    remote_manifest_source: Option<(&Client, &[Url])>,
+    config: &ProxyConfig,
+    profile: &mut AssetMapProfile,
 ) -> io::Result<AssetMap> {
     let mut asset_map = HashMap::new();
     let mut tasks = Vec::new();
 
-    for path in list_files(client_folder).await? {
+    // Each parse holds its pack open, so bound them to stay under the OS file limit
+    let pack_parse_semaphore = Arc::new(Semaphore::new(config.max_concurrent_pack_parses));
+
+    // Directory listing order depends on the filesystem, so sort the files to make precedence
+    // the same on every machine:
+    // - override files beat everything else
+    // - assets in later client folders beat assets in earlier ones
+    // - loose files beat in-pack assets
+    // - the first pack in path order wins when several packs contain the same asset
+    // - the last manifest in path order wins when a folder contains several manifests
+    // - generated manifest.txt and manifest.crc files beat loose and in-pack copies
+    let walk_start = Instant::now();
+    let mut paths = list_files(client_folder).await?;
+    paths.sort();
+    profile.directory_walk_ms += walk_start.elapsed().as_millis() as u64;
+
+    for path in paths {
         if let Some(extension) = path.extension() {
             if extension == "pack" {
-                tasks.push(spawn(list_assets_in_pack(path)));
+                profile.packs += 1;
+
+                // Listing every asset in a huge pack before inserting them would hold two copies
+                // of its names in memory, so stream those packs into the map after the loose files
+                let pack_len = metadata(&path).await?.len();
+                if pack_len > STREAMED_PACK_SIZE {
+                    tasks.push(PackParse::Streamed(path));
+                    continue;
+                }
+
+                let pack_parse_semaphore = Arc::clone(&pack_parse_semaphore);
+                tasks.push(PackParse::Listed(spawn(async move {
+                    let _permit = pack_parse_semaphore
+                        .acquire_owned()
+                        .await
+                        .expect("Pack parse semaphore closed");
+                    let parse_start = Instant::now();
+                    let result = list_assets_in_pack(path).await;
+                    (parse_start.elapsed(), result)
+                })));
                 continue;
             }
         }
 
         // Exclude extraneous files exactly named "manifest.txt" because we rename the
-        // real manifests to "manifest.txt"
+        // real manifests to "manifest.txt". Loose "manifest.crc" files are excluded too, so the
+        // CRC generated from the merged manifest is always the one served.
         if path
             .file_name()
             .map(|file_name| file_name == MANIFEST_FILE_NAME)
             .unwrap_or(false)
+            || file_name_ends_with(&path, MANIFEST_CRC_FILE_NAME)
         {
             continue;
         }
 
+        let read_start = Instant::now();
         let mut file_data = read(&path).await?;
+        profile.loose_file_ms += read_start.elapsed().as_millis() as u64;
 
         let path_without_prefix = path.strip_prefix(client_folder).unwrap().to_path_buf();
         if file_name_ends_with(&path_without_prefix, MANIFEST_SUFFIX) {
+            let remote_start = Instant::now();
             let compressed_manifest_path =
                 path_without_prefix.with_file_name(COMPRESSED_MANIFEST_FILE_NAME);
 
-            let mut remote_manifest = if let Some(manifest_path_str) =
-                compressed_manifest_path.to_str()
+            let mut remote_manifest = if let (
+                Some(manifest_path_str),
+                Some((http_client, game_server_urls)),
+            ) =
+                (compressed_manifest_path.to_str(), remote_manifest_source)
             {
                 let path_without_slashes = manifest_path_str.replace('\\', "/");
-                let remote_data =
-                    request_remote_asset(&path_without_slashes, http_client, game_server_url)
-                        .await
-                        .map(|manifest| manifest.to_vec());
-                if let Ok(remote_manifest) = remote_data {
-                    decompress_asset_response(remote_manifest)
-                        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?
-                } else {
-                    Vec::new()
+                let remote_data = request_remote_asset(
+                    &path_without_slashes,
+                    http_client,
+                    game_server_urls,
+                    config.max_remote_asset_size,
+                )
+                .await
+                .map(|manifest| manifest.to_vec());
+                match remote_data {
+                    Ok(remote_manifest) => decompress_asset_response(remote_manifest)
+                        .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?,
+
+                    // Merging nothing changes the manifest CRC, which can desync the client
+                    Err(status) if config.strict_remote_manifests => {
+                        return Err(io::Error::other(format!(
+                            "Remote manifest {} responded with status {}",
+                            path_without_slashes, status
+                        )));
+                    }
+                    Err(status) => {
+                        warn!(
+                            "Remote manifest {} responded with status {}, so only the local manifest is used",
+                            path_without_slashes, status
+                        );
+                        Vec::new()
+                    }
                 }
             } else {
                 Vec::new()
             };
+            profile.remote_manifest_ms += remote_start.elapsed().as_millis() as u64;
 
             file_data.append(&mut remote_manifest);
             let crc = crc32fast::hash(&file_data);
@@ -201,53 +644,284 @@ async fn build_asset_map(
                 AssetLocator {
                     crc,
                     kind: AssetLocatorKind::Memory(MemoryAssetLocator { data: file_data }),
+                    is_override: false,
+                    is_compressed: false,
                 },
             );
 
-            let manifest_crc_path = path_without_prefix.with_file_name(MANIFEST_CRC_FILE_NAME);
-            let crc_file_data = crc.to_string().as_bytes().to_vec();
+            insert_manifest_crc(&mut asset_map, &path_without_prefix, crc, false);
+        } else {
+            let hash_start = Instant::now();
+            let (crc, is_compressed) = loose_file_crc(&path, &file_data);
+            profile.loose_file_ms += hash_start.elapsed().as_millis() as u64;
+            profile.loose_files += 1;
+
+            // Always overwrite in-pack assets with assets outside a pack
             asset_map.insert(
-                manifest_crc_path,
+                path_without_prefix,
                 AssetLocator {
-                    crc: crc32fast::hash(&crc_file_data),
-                    kind: AssetLocatorKind::Memory(MemoryAssetLocator {
-                        data: crc_file_data,
+                    crc,
+                    kind: AssetLocatorKind::File(FileAssetLocator {
+                        path: Arc::new(path),
+                        data_offset: 0,
+                        size: file_data.len() as u32,
                     }),
+                    is_override: false,
+                    is_compressed,
                 },
             );
-        } else if !file_name_ends_with(&path_without_prefix, MANIFEST_CRC_FILE_NAME) {
-            let crc = crc32fast::hash(&file_data);
+        }
+    }
+
+    // Packs are inserted in path order either way so that the first pack still wins
+    for task in tasks {
+        match task {
+            PackParse::Listed(task) => {
+                let (parse_duration, result) = task.await?;
+                profile.pack_parse_ms += parse_duration.as_millis() as u64;
+                let (path, assets) = result?;
+                let path = Arc::new(path);
+                for asset in assets {
+                    insert_pack_asset(&mut asset_map, &path, asset);
+                }
+            }
+            PackParse::Streamed(path) => {
+                let parse_start = Instant::now();
+                let path = Arc::new(path);
+                visit_assets_in_pack(&path, |asset| {
+                    insert_pack_asset(&mut asset_map, &path, asset)
+                })
+                .await?;
+                profile.pack_parse_ms += parse_start.elapsed().as_millis() as u64;
+            }
+        }
+    }
+
+    Ok(asset_map)
+}
+
+#[instrument(skip_all, fields(client_folders = ?client_folders, assets = Empty))]
+async fn build_asset_map(
+    client_folders: &[PathBuf],
+    remote_manifest_source: Option<(&Client, &[Url])>,
+    config: &ProxyConfig,
+) -> io::Result<(AssetMap, AssetMapProfile)> {
+    let start = Instant::now();
+    let mut profile = AssetMapProfile::default();
+
+    // Layers replace whole entries, so an update folder's packed asset beats a base loose file
+    let mut asset_map = HashMap::new();
+    for client_folder in client_folders {
+        asset_map.extend(
+            build_layer_asset_map(client_folder, remote_manifest_source, config, &mut profile)
+                .await?,
+        );
+    }
+
+    // Overrides are added last so that they replace both loose and in-pack assets
+    if let Some(override_folder) = config.asset_override_dir.as_deref() {
+        let walk_start = Instant::now();
+        let mut override_paths = list_files(override_folder).await?;
+        override_paths.sort();
+        profile.directory_walk_ms += walk_start.elapsed().as_millis() as u64;
+
+        for path in override_paths {
+            // The manifest CRC is always derived from the manifest so that they can't disagree
+            if file_name_ends_with(&path, MANIFEST_CRC_FILE_NAME) {
+                continue;
+            }
+
+            let hash_start = Instant::now();
+            let file_data = read(&path).await?;
+            let (crc, is_compressed) = loose_file_crc(&path, &file_data);
+            profile.loose_file_ms += hash_start.elapsed().as_millis() as u64;
+            profile.loose_files += 1;
+            let path_without_prefix = path.strip_prefix(override_folder).unwrap().to_path_buf();
+            if path_without_prefix
+                .file_name()
+                .map(|file_name| file_name == MANIFEST_FILE_NAME)
+                .unwrap_or(false)
+            {
+                insert_manifest_crc(&mut asset_map, &path_without_prefix, crc, true);
+            }
 
-            // Always overwrite in-pack assets with assets outside a pack
             asset_map.insert(
                 path_without_prefix,
                 AssetLocator {
                     crc,
                     kind: AssetLocatorKind::File(FileAssetLocator {
-                        path,
+                        path: Arc::new(path),
                         data_offset: 0,
                         size: file_data.len() as u32,
                     }),
+                    is_override: true,
+                    is_compressed,
                 },
             );
         }
     }
 
-    for task in tasks {
-        let (path, assets) = task.await??;
-        for asset in assets {
-            asset_map.entry(asset.name).or_insert(AssetLocator {
-                crc: asset.crc,
-                kind: AssetLocatorKind::File(FileAssetLocator {
-                    path: path.clone(),
-                    data_offset: asset.data_offset,
-                    size: asset.size,
-                }),
-            });
+    Span::current().record("assets", asset_map.len());
+    profile.assets = asset_map.len();
+    profile.total_ms = start.elapsed().as_millis() as u64;
+    Ok((asset_map, profile))
+}
+
+// Each manifest line starts with the asset name and its CRC, separated by commas
+fn manifest_entries(manifest: &[u8]) -> impl Iterator<Item = (&str, u32)> {
+    manifest
+        .split(|&byte| byte == b'\n')
+        .filter_map(|line| std::str::from_utf8(line).ok())
+        .filter_map(|line| {
+            let mut fields = line.trim().split(',');
+            let name = fields.next()?.trim();
+            let crc = fields.next()?.trim().parse::<u32>().ok()?;
+            (!name.is_empty()).then_some((name, crc))
+        })
+}
+
+pub async fn missing_manifest_assets(
+    client_folders: &[PathBuf],
+    game_server_urls: &[Url],
+    config: &ProxyConfig,
+) -> io::Result<Vec<(String, u32)>> {
+    // Merge the remote manifests because they list the assets that a partial client is missing
+    let http_client = remote_asset_client(config, game_server_urls)?;
+    let (asset_map, _) = build_asset_map(
+        client_folders,
+        Some((&http_client, game_server_urls)),
+        config,
+    )
+    .await?;
+
+    let mut missing_assets = Vec::new();
+    for (manifest_path, asset_locator) in &asset_map {
+        let AssetLocatorKind::Memory(manifest) = &asset_locator.kind else {
+            continue;
+        };
+        if manifest_path.file_name() != Some(OsStr::new(MANIFEST_FILE_NAME)) {
+            continue;
+        }
+
+        for (name, crc) in manifest_entries(&manifest.data) {
+            let asset_name = manifest_path.with_file_name(name);
+            if !asset_map.contains_key(&asset_name) {
+                missing_assets.push((asset_name.to_string_lossy().replace('\\', "/"), crc));
+            }
         }
     }
 
-    Ok(asset_map)
+    missing_assets.sort();
+    missing_assets.dedup();
+    Ok(missing_assets)
+}
+
+pub async fn check_client_packs(client_folders: &[PathBuf]) -> io::Result<Vec<PackStatus>> {
+    let mut pack_paths = Vec::new();
+    for client_folder in client_folders {
+        for path in list_files(client_folder).await? {
+            if path.extension() == Some(OsStr::new("pack")) {
+                pack_paths.push((client_folder.clone(), path));
+            }
+        }
+    }
+
+    // Packs can be large, so don't read all of them at the same time
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PACK_CHECKS));
+    let mut checks = JoinSet::new();
+    for (client_folder, pack_path) in pack_paths {
+        let semaphore = Arc::clone(&semaphore);
+        checks.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("Pack check semaphore closed");
+            // Only the count is needed, so don't keep the parsed assets around
+            let mut assets = 0;
+            let result = visit_assets_in_pack(&pack_path, |_| assets += 1)
+                .await
+                .map(|()| assets);
+            (client_folder, pack_path, result)
+        });
+    }
+
+    let mut statuses = Vec::new();
+    while let Some(check) = checks.join_next().await {
+        let (client_folder, pack_path, result) = check?;
+        let path = pack_path
+            .strip_prefix(&client_folder)
+            .unwrap_or(&pack_path)
+            .to_path_buf();
+        statuses.push(match result {
+            Ok(assets) => PackStatus {
+                client_folder,
+                path,
+                parsed: true,
+                assets,
+                error: None,
+            },
+            Err(err) => PackStatus {
+                client_folder,
+                path,
+                parsed: false,
+                assets: 0,
+                error: Some(err.to_string()),
+            },
+        });
+    }
+
+    statuses.sort_by(|status1, status2| {
+        (&status1.client_folder, &status1.path).cmp(&(&status2.client_folder, &status2.path))
+    });
+    Ok(statuses)
+}
+
+// Diagnostic only: tiny assets are still served like any other asset
+fn tiny_assets(asset_map: &AssetMap, config: &ProxyConfig) -> Vec<TinyAsset> {
+    if config.tiny_asset_thresholds.is_empty() {
+        return Vec::new();
+    }
+
+    let mut tiny_assets: Vec<TinyAsset> = asset_map
+        .iter()
+        .filter_map(|(name, asset_locator)| {
+            let extension = name.extension()?.to_str()?.to_lowercase();
+            let threshold = *config.tiny_asset_thresholds.get(&extension)?;
+            let size = match &asset_locator.kind {
+                AssetLocatorKind::Memory(locator) => locator.data.len() as u64,
+                AssetLocatorKind::File(locator) => locator.size as u64,
+            };
+            (size < threshold).then(|| TinyAsset {
+                name: name.clone(),
+                size,
+                threshold,
+            })
+        })
+        .collect();
+    tiny_assets.sort_by(|asset1, asset2| asset1.name.cmp(&asset2.name));
+    tiny_assets
+}
+
+pub async fn find_client_tiny_assets(
+    client_folders: &[PathBuf],
+    config: &ProxyConfig,
+) -> io::Result<Vec<TinyAsset>> {
+    let (asset_map, _) = build_asset_map(client_folders, None, config).await?;
+    Ok(tiny_assets(&asset_map, config))
+}
+
+pub async fn profile_client_asset_map(
+    client_folders: &[PathBuf],
+    game_server_urls: Option<Vec<Url>>,
+    config: &ProxyConfig,
+) -> io::Result<AssetMapProfile> {
+    let http_client = game_server_urls
+        .as_deref()
+        .map(|game_server_urls| remote_asset_client(config, game_server_urls))
+        .transpose()?;
+    let remote_manifest_source = http_client.as_ref().zip(game_server_urls.as_deref());
+    let (_, profile) = build_asset_map(client_folders, remote_manifest_source, config).await?;
+    Ok(profile)
 }
 
 fn decompose_extension(asset_name: &std::path::Path) -> (PathBuf, bool, Option<u32>) {
@@ -256,13 +930,16 @@ fn decompose_extension(asset_name: &std::path::Path) -> (PathBuf, bool, Option<u
         .map(|extension| extension.to_os_string().into_string().ok())
         .unwrap_or(None);
     let (non_crc_asset_name, crc) = if let Some(extension_str) = possible_extension_str {
-        let extension_split = extension_str.rsplit_once(CRC_EXTENSION_SEPARATOR);
+        let extension_split = extension_str.rsplit_once(CRC_EXTENSION_SEPARATOR).and_then(
+            |(real_extension, crc_str)| {
+                crc_str.parse::<u32>().ok().map(|crc| (real_extension, crc))
+            },
+        );
 
-        if let Some((real_extension, crc_str)) = extension_split {
-            (
-                asset_name.with_extension(real_extension),
-                crc_str.parse::<u32>().ok(),
-            )
+        // Extensions may contain the separator themselves (as some audio assets do), so only
+        // treat the suffix as a CRC when it is actually a number
+        if let Some((real_extension, crc)) = extension_split {
+            (asset_name.with_extension(real_extension), Some(crc))
         } else {
             (asset_name.to_path_buf(), None)
         }
@@ -283,6 +960,60 @@ fn decompose_extension(asset_name: &std::path::Path) -> (PathBuf, bool, Option<u
     (uncompressed_asset_name, compressed, crc)
 }
 
+fn is_transient_read_error(err: &io::Error) -> bool {
+    #[cfg(windows)]
+    if err
+        .raw_os_error()
+        .is_some_and(|code| TRANSIENT_WINDOWS_ERRORS.contains(&code))
+    {
+        return true;
+    }
+
+    matches!(
+        err.kind(),
+        ErrorKind::PermissionDenied | ErrorKind::Interrupted | ErrorKind::WouldBlock
+    )
+}
+
+async fn read_file_asset(locator: &FileAssetLocator) -> io::Result<Vec<u8>> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .open(locator.path.as_ref())
+        .await?;
+    file.seek(SeekFrom::Start(locator.data_offset)).await?;
+
+    let mut file_buffer = vec![0; locator.size as usize];
+    file.read_exact(&mut file_buffer).await?;
+    Ok(file_buffer)
+}
+
+// Antivirus scanners on Windows briefly lock files, so retry before the asset handler falls back
+// to the remote server
+async fn read_with_retries<F, Fut>(path: &std::path::Path, mut read_fn: F) -> io::Result<Vec<u8>>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = io::Result<Vec<u8>>>,
+{
+    let mut retries = 0;
+    loop {
+        match read_fn().await {
+            Ok(file_buffer) => return Ok(file_buffer),
+            Err(err) if retries < FILE_READ_RETRIES && is_transient_read_error(&err) => {
+                retries += 1;
+                warn!(
+                    "Retrying read of {} after transient error (attempt {} of {}): {}",
+                    path.display(),
+                    retries,
+                    FILE_READ_RETRIES,
+                    err
+                );
+                sleep(FILE_READ_RETRY_DELAY).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 async fn build_local_asset_response(
     asset_locator: &AssetLocator,
     compress: bool,
@@ -292,17 +1023,20 @@ async fn build_local_asset_response(
     let mut file_buffer = match &asset_locator.kind {
         AssetLocatorKind::Memory(locator) => locator.data.clone(),
         AssetLocatorKind::File(locator) => {
-            // Read file from local client folder
-            let mut file = OpenOptions::new().read(true).open(&locator.path).await?;
-            file.seek(SeekFrom::Start(locator.data_offset)).await?;
-
-            let mut file_buffer = vec![0; locator.size as usize];
-            file.read_exact(&mut file_buffer).await?;
-            file_buffer
+            read_with_retries(&locator.path, || read_file_asset(locator)).await?
         }
     };
 
-    if compress {
+    // Pre-compressed files already have the header, so they only need work when the client wants
+    // the raw asset
+    if asset_locator.is_compressed {
+        if compress {
+            buffer.append(&mut file_buffer);
+        } else {
+            buffer = decompress_asset_response(file_buffer)
+                .map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        }
+    } else if compress {
         buffer.write_u32(COMPRESSED_MAGIC).await?;
         buffer.write_u32(file_buffer.len() as u32).await?;
         buffer.append(&mut compress_to_vec_zlib(
@@ -316,10 +1050,12 @@ async fn build_local_asset_response(
     Ok(buffer)
 }
 
-async fn request_remote_asset(
+#[instrument(level = "debug", skip(http_client), fields(%game_server_url, status = Empty))]
+async fn request_remote_asset_from_server(
     path_and_query: &str,
-    http_client: &Arc<Client>,
-    game_server_url: &Arc<Url>,
+    http_client: &Client,
+    game_server_url: &Url,
+    max_size: u64,
 ) -> Result<Bytes, StatusCode> {
     let url = game_server_url
         .join("assets/")
@@ -332,115 +1068,2000 @@ async fn request_remote_asset(
         .await
         .map_err(|err| err.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?;
 
+    Span::current().record("status", response.status().as_u16());
     match response.status() {
-        StatusCode::OK => Ok(response
-            .bytes()
-            .await
-            .map_err(|err| err.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?),
+        StatusCode::OK => read_remote_body(response, path_and_query, max_size).await,
         status_code => Err(status_code),
     }
 }
 
-async fn retrieve_asset(
-    asset_name: PathBuf,
-    http_client: Arc<Client>,
-    asset_map: Arc<AssetMap>,
-    game_server_url: Arc<Url>,
-    request: Request,
+async fn read_remote_body(
+    mut response: reqwest::Response,
+    path_and_query: &str,
+    max_size: u64,
 ) -> Result<Bytes, StatusCode> {
-    // SECURITY: Ensure that the path is within the assets cache before returning any data.
-    // Reject all paths containing anything other than normal folder names (e.g. paths containing
-    // the parent directory or the root directory).
-    let is_invalid_path = asset_name
-        .components()
-        .any(|component| !matches!(component, Component::Normal(_)));
-    if is_invalid_path {
-        return Err(StatusCode::BAD_REQUEST);
+    let expected_len = response.content_length();
+    if expected_len.is_some_and(|expected_len| expected_len > max_size) {
+        warn!(
+            "Remote asset {} is larger than the {} byte limit",
+            path_and_query, max_size
+        );
+        return Err(StatusCode::BAD_GATEWAY);
     }
 
-    let (uncompressed_asset_name, compress, queried_crc) = decompose_extension(&asset_name);
-
-    let possible_file_data = if let Some(asset_locator) = asset_map.get(&uncompressed_asset_name) {
-        let crc = queried_crc.unwrap_or(asset_locator.crc);
-        if crc == asset_locator.crc {
-            build_local_asset_response(asset_locator, compress)
-                .await
-                .ok()
-        } else {
-            None
+    // Read in chunks so that a server without a Content-Length can't send an unbounded body
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|err| err.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR))?
+    {
+        if (body.len() + chunk.len()) as u64 > max_size {
+            warn!(
+                "Remote asset {} is larger than the {} byte limit",
+                path_and_query, max_size
+            );
+            return Err(StatusCode::BAD_GATEWAY);
         }
-    } else {
-        None
-    };
 
-    if let Some(file_data) = possible_file_data {
-        Ok(file_data.into())
-    } else {
-        let request_path = request.uri().path();
-        let path_and_query = request
-            .uri()
-            .path_and_query()
-            .map(|path_and_query| {
-                path_and_query
-                    .path()
-                    .strip_prefix("/assets/")
-                    .expect("Assets request is missing /assets prefix")
-            })
-            .unwrap_or(request_path);
-        request_remote_asset(path_and_query, &http_client, &game_server_url).await
+        body.extend_from_slice(&chunk);
     }
-}
 
-fn is_name_hash(component: &OsStr) -> bool {
-    let is_hash_length = component.len() == 3;
-    is_hash_length
-        && if let Ok(comp_str) = component.to_os_string().into_string() {
-            comp_str.parse::<u16>().is_ok()
-        } else {
-            false
+    if let Some(expected_len) = expected_len {
+        if body.len() as u64 != expected_len {
+            warn!(
+                "Remote asset {} has {} bytes but its Content-Length is {}",
+                path_and_query,
+                body.len(),
+                expected_len
+            );
+            return Err(StatusCode::BAD_GATEWAY);
         }
+    }
+
+    Ok(Bytes::from(body))
 }
 
-async fn asset_handler(
-    Path(asset): Path<PathBuf>,
-    State((http_client, asset_map, game_server_url)): State<(Arc<Client>, Arc<AssetMap>, Arc<Url>)>,
-    request: Request,
+async fn request_remote_asset(
+    path_and_query: &str,
+    http_client: &Client,
+    game_server_urls: &[Url],
+    max_size: u64,
 ) -> Result<Bytes, StatusCode> {
-    let is_first_component_name_hash = asset.iter().next().map(is_name_hash).unwrap_or(false);
+    let mut last_status_code = StatusCode::BAD_GATEWAY;
 
-    // Ignore the name hash if it is included
-    let asset_name = if is_first_component_name_hash {
-        let mut components = asset.components();
-        components.next();
-        components.as_path().to_path_buf()
-    } else {
-        asset
-    };
+    // Try each mirror in order when a server is unreachable or failing, but don't retry
+    // responses that every mirror would agree on, like a missing asset
+    for game_server_url in game_server_urls {
+        match request_remote_asset_from_server(
+            path_and_query,
+            http_client,
+            game_server_url,
+            max_size,
+        )
+        .await
+        {
+            Ok(remote_data) => return Ok(remote_data),
+            Err(status_code) if status_code.is_server_error() => {
+                last_status_code = status_code;
+            }
+            Err(status_code) => return Err(status_code),
+        }
+    }
 
-    retrieve_asset(asset_name, http_client, asset_map, game_server_url, request).await
+    Err(last_status_code)
 }
 
-async fn start_proxy(listener: TcpListener, app: Router) {
-    serve(listener, app).await.expect("Unable to start proxy");
+// Assets requested with a CRC never change, so they're the only remote assets cached on disk
+pub fn remote_cache_file(
+    remote_cache_dir: &std::path::Path,
+    asset_name: &std::path::Path,
+) -> Option<PathBuf> {
+    let (_, _, queried_crc) = decompose_extension(asset_name);
+    queried_crc.map(|_| remote_cache_dir.join(asset_name))
 }
 
-pub async fn prepare_proxy(
-    port: u16,
-    client_folder: &std::path::Path,
-    game_server_uri: Url,
-) -> io::Result<impl Future<Output = ()>> {
-    let client = Client::new();
-    let client_arc = Arc::new(client);
-    let game_server_url_arc = Arc::new(game_server_uri.clone());
-    let asset_map = build_asset_map(client_folder, &client_arc, &game_server_url_arc).await?;
-    let app = Router::new()
-        .route("/assets/*asset", get(asset_handler))
-        .with_state((client_arc, Arc::new(asset_map), game_server_url_arc));
+fn matches_crc(data: &[u8], compressed: bool, crc: u32) -> bool {
+    if compressed {
+        decompress_asset_response(data.to_vec())
+            .is_ok_and(|uncompressed_data| crc32fast::hash(&uncompressed_data) == crc)
+    } else {
+        crc32fast::hash(data) == crc
+    }
+}
 
-    let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
-    println!(
+pub async fn cache_remote_asset(
+    remote_cache_dir: &std::path::Path,
+    asset_name: &std::path::Path,
+    data: &[u8],
+) -> io::Result<()> {
+    static NEXT_PART_ID: AtomicU64 = AtomicU64::new(0);
+
+    let (_, compressed, queried_crc) = decompose_extension(asset_name);
+    let Some(crc) = queried_crc else {
+        return Err(io::Error::new(
+            ErrorKind::InvalidInput,
+            "Only assets requested with a CRC can be cached",
+        ));
+    };
+
+    // A bad response would otherwise be served from the cache on every later request
+    if !matches_crc(data, compressed, crc) {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Remote asset does not match its CRC {}", crc),
+        ));
+    }
+
+    // Write to a unique file first so that a request never reads a partially-written asset
+    let cache_file = remote_cache_dir.join(asset_name);
+    if let Some(parent) = cache_file.parent() {
+        create_dir_all(parent).await?;
+    }
+    let mut part_file = cache_file.clone().into_os_string();
+    part_file.push(format!(
+        ".{}.part",
+        NEXT_PART_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+    write(&part_file, data).await?;
+    rename(&part_file, &cache_file).await
+}
+
+fn asset_etag(crc: u32, compress: bool) -> String {
+    // Compressed and uncompressed responses are different representations of the same asset
+    if compress {
+        format!("\"{}-{}\"", crc, COMPRESSED_EXTENSION)
+    } else {
+        format!("\"{}\"", crc)
+    }
+}
+
+fn if_none_match_contains(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+fn validate_asset_path(
+    asset_name: &std::path::Path,
+    config: &ProxyConfig,
+) -> Result<(), StatusCode> {
+    // There's no asset listing, so a path without a name can't refer to anything
+    if asset_name.components().next().is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    // Reject pathological paths before doing any work with them
+    if asset_name.as_os_str().len() > config.max_asset_path_length {
+        return Err(StatusCode::URI_TOO_LONG);
+    }
+    if asset_name.components().count() > config.max_asset_path_components {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // SECURITY: Ensure that the path is within the assets cache before returning any data.
+    // Reject all paths containing anything other than normal folder names (e.g. paths containing
+    // the parent directory or the root directory).
+    let is_invalid_path = asset_name
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)));
+    if is_invalid_path {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(())
+}
+
+fn lowercase_asset_name(asset_name: &std::path::Path) -> PathBuf {
+    PathBuf::from(asset_name.to_string_lossy().to_lowercase())
+}
+
+fn case_insensitive_names(asset_map: &AssetMap, config: &ProxyConfig) -> CaseInsensitiveNames {
+    let mut names = HashMap::new();
+    if !config.case_insensitive_lookup {
+        return names;
+    }
+
+    // The client can't tell apart names that only differ by case, so pick one of them the same
+    // way every time
+    for asset_name in asset_map.keys() {
+        match names.entry(lowercase_asset_name(asset_name)) {
+            Entry::Occupied(mut entry) => {
+                if asset_name < entry.get() {
+                    entry.insert(asset_name.clone());
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(asset_name.clone());
+            }
+        }
+    }
+
+    names
+}
+
+pub fn always_remote_regex(patterns: &[String]) -> Result<Option<Regex>, regex::Error> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    // Patterns are globs where "*" matches any characters, including folder separators
+    let alternatives: Vec<String> = patterns
+        .iter()
+        .map(|pattern| regex::escape(pattern).replace(r"\*", ".*"))
+        .collect();
+    RegexBuilder::new(&format!("^(?:{})$", alternatives.join("|")))
+        .case_insensitive(true)
+        .build()
+        .map(Some)
+}
+
+fn is_always_remote(uncompressed_asset_name: &std::path::Path, config: &ProxyConfig) -> bool {
+    config.always_remote.as_ref().is_some_and(|always_remote| {
+        always_remote.is_match(&uncompressed_asset_name.to_string_lossy().replace('\\', "/"))
+    })
+}
+
+fn find_local_asset<'a>(
+    asset_map: &'a AssetMap,
+    case_insensitive_names: &CaseInsensitiveNames,
+    uncompressed_asset_name: &std::path::Path,
+    queried_crc: Option<u32>,
+) -> Result<&'a AssetLocator, RemoteReason> {
+    // The client runs on Windows, where names are case-insensitive, so fall back to any asset
+    // whose name only differs by case
+    let asset_locator = asset_map
+        .get(uncompressed_asset_name)
+        .or_else(|| {
+            case_insensitive_names
+                .get(&lowercase_asset_name(uncompressed_asset_name))
+                .and_then(|asset_name| asset_map.get(asset_name))
+        })
+        .ok_or(RemoteReason::NotFound)?;
+
+    // Overrides always win, even over the version of an asset that the client asked for
+    match queried_crc {
+        Some(requested_crc) if requested_crc != asset_locator.crc && !asset_locator.is_override => {
+            Err(RemoteReason::CrcMismatch {
+                requested_crc,
+                local_crc: asset_locator.crc,
+            })
+        }
+        _ => Ok(asset_locator),
+    }
+}
+
+#[instrument(level = "debug", skip(state, request), fields(crc_match = Empty, source = Empty))]
+async fn retrieve_asset(
+    asset_name: PathBuf,
+    state: ProxyState,
+    request: Request,
+) -> Result<Response, StatusCode> {
+    let (uncompressed_asset_name, compress, queried_crc) = decompose_extension(&asset_name);
+    let (max_remote_asset_size, is_always_remote, remote_cache_dir) = {
+        let config = state.config.read().expect("Unable to lock proxy config");
+        validate_asset_path(&asset_name, &config)?;
+        (
+            config.max_remote_asset_size,
+            is_always_remote(&uncompressed_asset_name, &config),
+            config.remote_cache_dir.clone(),
+        )
+    };
+
+    let possible_asset_locator = if is_always_remote {
+        None
+    } else {
+        find_local_asset(
+            &state.asset_map,
+            &state.case_insensitive_names,
+            &uncompressed_asset_name,
+            queried_crc,
+        )
+        .ok()
+    };
+    Span::current().record("crc_match", possible_asset_locator.is_some());
+
+    if let Some(asset_locator) = possible_asset_locator {
+        let etag = asset_etag(asset_locator.crc, compress);
+
+        // Assets requested with a CRC never change, while other assets (including overrides
+        // served in place of a requested CRC) must be revalidated
+        let cache_control = if queried_crc == Some(asset_locator.crc) {
+            IMMUTABLE_CACHE_CONTROL
+        } else {
+            REVALIDATE_CACHE_CONTROL
+        };
+        let is_not_modified = if_none_match_contains(request.headers(), &etag);
+        let headers = [(ETAG, etag), (CACHE_CONTROL, cache_control.to_string())];
+
+        if is_not_modified {
+            return Ok((StatusCode::NOT_MODIFIED, headers).into_response());
+        }
+
+        // Compressing is the expensive part of serving a local asset, so reuse earlier results
+        let is_cacheable = compress && !asset_locator.is_compressed;
+        let cache_key = (uncompressed_asset_name.clone(), asset_locator.crc);
+        let cached_data = if is_cacheable {
+            state
+                .compression_cache
+                .lock()
+                .expect("Unable to lock compression cache")
+                .get(&cache_key)
+        } else {
+            None
+        };
+        if let Some(cached_data) = cached_data {
+            Span::current().record("source", "local");
+            return Ok((headers, cached_data).into_response());
+        }
+
+        match build_local_asset_response(asset_locator, compress).await {
+            Ok(file_data) => {
+                Span::current().record("source", "local");
+                let file_data = Bytes::from(file_data);
+                if is_cacheable {
+                    state
+                        .compression_cache
+                        .lock()
+                        .expect("Unable to lock compression cache")
+                        .insert(cache_key, file_data.clone());
+                }
+                return Ok((headers, file_data).into_response());
+            }
+            Err(err) => warn!("Unable to read local asset: {}", err),
+        }
+    }
+
+    Span::current().record("source", "remote");
+
+    // Remember which assets were missing locally, without letting a fully-remote client grow the
+    // set forever
+    {
+        let mut remote_assets = state
+            .remote_assets
+            .lock()
+            .expect("Unable to lock remote assets");
+        if remote_assets.len() < MAX_TRACKED_REMOTE_ASSETS {
+            remote_assets.insert(uncompressed_asset_name.to_string_lossy().replace('\\', "/"));
+        }
+    }
+
+    // Reject rather than panic if a request without the prefix ever reaches this handler
+    let path_and_query = request
+        .uri()
+        .path()
+        .strip_prefix("/assets/")
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let cache_headers = [(CACHE_CONTROL, NO_STORE_CACHE_CONTROL)];
+    let cache_file = remote_cache_dir
+        .as_deref()
+        .and_then(|remote_cache_dir| remote_cache_file(remote_cache_dir, &asset_name));
+    if let Some(cache_file) = cache_file {
+        if let Ok(cached_data) = read(&cache_file).await {
+            Span::current().record("source", "disk_cache");
+            return Ok((cache_headers, cached_data).into_response());
+        }
+    }
+
+    let remote_data = request_remote_asset(
+        path_and_query,
+        &state.http_client,
+        &state.game_server_urls,
+        max_remote_asset_size,
+    )
+    .await;
+    match remote_data {
+        Ok(remote_data) => {
+            // Write to the cache in the background so that the client doesn't wait on the disk
+            if let (Some(remote_cache_dir), Some(_)) = (remote_cache_dir, queried_crc) {
+                let remote_data = remote_data.clone();
+                spawn(async move {
+                    if let Err(err) =
+                        cache_remote_asset(&remote_cache_dir, &asset_name, &remote_data).await
+                    {
+                        warn!(
+                            "Unable to cache remote asset {}: {}",
+                            asset_name.display(),
+                            err
+                        );
+                    }
+                });
+            }
+
+            Ok((cache_headers, remote_data).into_response())
+        }
+        Err(status_code) => Ok((status_code, cache_headers).into_response()),
+    }
+}
+
+fn is_name_hash(component: &OsStr) -> bool {
+    // Check the decoded characters rather than the byte length, and require digits only because
+    // parsing alone would also accept signs like "+12"
+    component
+        .to_str()
+        .map(|comp_str| {
+            comp_str.chars().count() == 3 && comp_str.chars().all(|char| char.is_ascii_digit())
+        })
+        .unwrap_or(false)
+}
+
+fn strip_name_hash(asset: PathBuf) -> PathBuf {
+    let is_first_component_name_hash = asset.iter().next().map(is_name_hash).unwrap_or(false);
+
+    // Ignore the name hash if it is included
+    if is_first_component_name_hash {
+        let mut components = asset.components();
+        components.next();
+        components.as_path().to_path_buf()
+    } else {
+        asset
+    }
+}
+
+fn requested_asset_name(asset: PathBuf, config: &ProxyConfig) -> PathBuf {
+    // The name hash check can mistake a real folder for a hash, so allow turning it off
+    if config.strip_name_hash {
+        strip_name_hash(asset)
+    } else {
+        asset
+    }
+}
+
+#[instrument(level = "debug", skip(state, request))]
+async fn asset_handler(
+    Path(asset): Path<PathBuf>,
+    State(state): State<ProxyState>,
+    request: Request,
+) -> Result<Response, StatusCode> {
+    let asset_name = {
+        let config = state.config.read().expect("Unable to lock proxy config");
+        requested_asset_name(asset, &config)
+    };
+    retrieve_asset(asset_name, state, request).await
+}
+
+fn proxy_router(state: ProxyState, debug_endpoints: bool) -> Router {
+    // GET /alive responds with 204 No Content and counts as activity, so anything that hits it
+    // keeps the proxy from being shut down as idle
+    let mut router = Router::new()
+        .route("/assets", get(asset_root_handler))
+        .route("/assets/", get(asset_root_handler))
+        .route("/assets/*asset", get(asset_handler))
+        .route("/alive", get(alive_handler));
+
+    // The proxy only listens on localhost, but keep the asset map private unless debugging
+    if debug_endpoints {
+        router = router.route("/debug/assets", get(debug_assets_handler));
+    }
+
+    router.fallback(fallback_handler).with_state(state)
+}
+
+async fn asset_root_handler() -> StatusCode {
+    StatusCode::NOT_FOUND
+}
+
+async fn fallback_handler(
+    State(state): State<ProxyState>,
+    request: Request,
+) -> (StatusCode, String) {
+    // Log requests the proxy doesn't handle yet so that support can see what the client wanted
+    warn!(
+        "Unhandled proxy request: {} {}",
+        request.method(),
+        request.uri()
+    );
+    (
+        StatusCode::NOT_FOUND,
+        state
+            .config
+            .read()
+            .expect("Unable to lock proxy config")
+            .not_found_message
+            .clone(),
+    )
+}
+
+async fn alive_handler(State(state): State<ProxyState>) -> StatusCode {
+    *state
+        .last_alive
+        .lock()
+        .expect("Unable to lock last alive time") = Some(Instant::now());
+    StatusCode::NO_CONTENT
+}
+
+async fn debug_assets_handler(State(state): State<ProxyState>) -> Json<Vec<AssetMapEntry>> {
+    let mut entries: Vec<AssetMapEntry> = state
+        .asset_map
+        .iter()
+        .map(|(name, asset_locator)| AssetMapEntry {
+            name: name.clone(),
+            crc: asset_locator.crc,
+            size: match &asset_locator.kind {
+                AssetLocatorKind::Memory(locator) => locator.data.len() as u64,
+                AssetLocatorKind::File(locator) => locator.size as u64,
+            },
+            source: AssetSource::from(&asset_locator.kind),
+        })
+        .collect();
+    entries.sort_by(|entry1, entry2| entry1.name.cmp(&entry2.name));
+    Json(entries)
+}
+
+pub async fn find_client_assets_by_crc(
+    client_folders: &[PathBuf],
+    crc: u32,
+    config: &ProxyConfig,
+) -> io::Result<Vec<String>> {
+    let (asset_map, _) = build_asset_map(client_folders, None, config).await?;
+    let mut asset_names: Vec<String> = asset_map
+        .iter()
+        .filter(|(_, asset_locator)| asset_locator.crc == crc)
+        .map(|(asset_name, _)| asset_name.to_string_lossy().replace('\\', "/"))
+        .collect();
+    asset_names.sort();
+    Ok(asset_names)
+}
+
+pub async fn resolve_client_asset(
+    client_folders: &[PathBuf],
+    asset_path: &str,
+    config: &ProxyConfig,
+) -> io::Result<AssetResolution> {
+    let asset_name = requested_asset_name(PathBuf::from(asset_path), config);
+    if let Err(status) = validate_asset_path(&asset_name, config) {
+        return Ok(AssetResolution::Rejected {
+            status: status.as_u16(),
+        });
+    }
+
+    // Remote manifests are not merged because resolving an asset must not make any requests
+    let (asset_map, _) = build_asset_map(client_folders, None, config).await?;
+    let case_insensitive_names = case_insensitive_names(&asset_map, config);
+    let (uncompressed_asset_name, compress, queried_crc) = decompose_extension(&asset_name);
+    if is_always_remote(&uncompressed_asset_name, config) {
+        return Ok(AssetResolution::Remote {
+            asset_name: uncompressed_asset_name,
+            reason: RemoteReason::AlwaysRemote,
+        });
+    }
+
+    Ok(
+        match find_local_asset(
+            &asset_map,
+            &case_insensitive_names,
+            &uncompressed_asset_name,
+            queried_crc,
+        ) {
+            Ok(asset_locator) => AssetResolution::Local {
+                crc: asset_locator.crc,
+                compress,
+                source: AssetSource::from(&asset_locator.kind),
+                asset_name: uncompressed_asset_name,
+            },
+            Err(reason) => AssetResolution::Remote {
+                asset_name: uncompressed_asset_name,
+                reason,
+            },
+        },
+    )
+}
+
+async fn start_proxy(
+    listener: TcpListener,
+    unix_listener: Option<UnixListener>,
+    app: Router,
+    ready_sender: oneshot::Sender<u16>,
+) {
+    // Dropping the set aborts the Unix socket server along with the proxy
+    let mut unix_server = JoinSet::new();
+    if let Some(unix_listener) = unix_listener {
+        unix_server.spawn(serve_unix(unix_listener, app.clone()));
+    }
+
+    let port = listener
+        .local_addr()
+        .expect("Listener has no address")
+        .port();
+    let server = serve(listener, app);
+
+    // The receiver is dropped if the launch was abandoned, so there's no one to notify
+    let _ = ready_sender.send(port);
+    server.await.expect("Unable to start proxy");
+}
+
+#[cfg(unix)]
+async fn serve_unix(listener: UnixListener, app: Router) {
+    // axum::serve only accepts TCP listeners, so serve each connection with hyper directly
+    let mut connections = JoinSet::new();
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                warn!("Unable to accept Unix socket connection: {}", err);
+                sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        connections.spawn(async move {
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                warn!("Error while serving Unix socket connection: {}", err);
+            }
+        });
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+// Stands in for the Unix listener on platforms without Unix domain sockets
+#[cfg(not(unix))]
+enum UnixListener {}
+
+#[cfg(not(unix))]
+async fn serve_unix(listener: UnixListener, _: Router) {
+    match listener {}
+}
+
+#[cfg(unix)]
+fn bind_unix_listener(path: &std::path::Path) -> io::Result<UnixListener> {
+    // Remove a socket left behind by an earlier session, but never a regular file
+    if std::fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+        std::fs::remove_file(path)?;
+    }
+
+    UnixListener::bind(path)
+}
+
+#[cfg(not(unix))]
+fn bind_unix_listener(_: &std::path::Path) -> io::Result<UnixListener> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "Unix domain sockets are not supported on this platform",
+    ))
+}
+
+fn redirect_policy(policy: ProxyRedirectPolicy, game_server_urls: &[Url]) -> Policy {
+    match policy {
+        ProxyRedirectPolicy::Follow => Policy::limited(MAX_REDIRECTS),
+        ProxyRedirectPolicy::SameHost => {
+            let game_server_hosts: Vec<Option<String>> = game_server_urls
+                .iter()
+                .map(|game_server_url| game_server_url.host_str().map(str::to_string))
+                .collect();
+            Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("Too many redirects")
+                } else if game_server_hosts
+                    .iter()
+                    .any(|host| attempt.url().host_str() == host.as_deref())
+                {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            })
+        }
+
+        // The redirect status is returned to the game client as-is when redirects aren't followed
+        ProxyRedirectPolicy::Disabled => Policy::none(),
+    }
+}
+
+fn bind_listener(port: u16, config: &ProxyConfig) -> io::Result<TcpListener> {
+    // The client opens many connections at once during startup, so allow a larger backlog than
+    // the default. Accepted connections inherit keep-alive from the listening socket. HTTP
+    // keep-alive is already enabled by the server, so the client can reuse its connections.
+    let socket = TcpSocket::new_v4()?;
+
+    // Match TcpListener::bind, which allows rebinding a port still in TIME_WAIT
+    #[cfg(not(windows))]
+    socket.set_reuseaddr(true)?;
+
+    socket.set_keepalive(config.tcp_keepalive)?;
+    socket.bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))?;
+    socket.listen(config.listen_backlog)
+}
+
+pub fn remote_asset_client(config: &ProxyConfig, game_server_urls: &[Url]) -> io::Result<Client> {
+    // Insecure: anyone between the launcher and the server can tamper with assets in this mode,
+    // so it's only meant for self-hosted servers with self-signed certificates
+    if config.accept_invalid_certs {
+        warn!("Accepting invalid TLS certificates from the game server");
+    }
+
+    Client::builder()
+        .user_agent(&config.user_agent)
+        .default_headers(config.remote_headers.clone())
+        .redirect(redirect_policy(config.redirect_policy, game_server_urls))
+        .danger_accept_invalid_certs(config.accept_invalid_certs)
+        .build()
+        .map_err(io::Error::other)
+}
+
+pub async fn fetch_remote_asset(
+    asset_path: &str,
+    http_client: &Client,
+    game_server_urls: &[Url],
+    config: &ProxyConfig,
+) -> Result<(PathBuf, Bytes), String> {
+    let asset_path = asset_path.replace('\\', "/");
+    let asset_name = requested_asset_name(PathBuf::from(&asset_path), config);
+    validate_asset_path(&asset_name, config)
+        .map_err(|status| format!("Rejected asset path with status {}", status))?;
+
+    // Fetch the same way the proxy does when an asset is missing locally
+    let remote_data = request_remote_asset(
+        &asset_path,
+        http_client,
+        game_server_urls,
+        config.max_remote_asset_size,
+    )
+    .await
+    .map_err(|status| format!("Remote server responded with status {}", status))?;
+    Ok((asset_name, remote_data))
+}
+
+pub async fn prepare_proxy(
+    port: u16,
+    client_folders: &[PathBuf],
+    game_server_uri: Url,
+    mirror_uris: Vec<Url>,
+    shared_config: Arc<RwLock<ProxyConfig>>,
+    remote_assets: Arc<Mutex<BTreeSet<String>>>,
+    last_alive: Arc<Mutex<Option<Instant>>>,
+) -> io::Result<(impl Future<Output = ()>, oneshot::Receiver<u16>)> {
+    let config = shared_config
+        .read()
+        .expect("Unable to lock proxy config")
+        .clone();
+    let mut game_server_urls = vec![game_server_uri];
+    game_server_urls.extend(mirror_uris);
+
+    let client_arc = Arc::new(remote_asset_client(&config, &game_server_urls)?);
+    let game_server_urls_arc = Arc::new(game_server_urls);
+    let (asset_map, profile) = build_asset_map(
+        client_folders,
+        Some((client_arc.as_ref(), game_server_urls_arc.as_slice())),
+        &config,
+    )
+    .await?;
+    info!(
+        "Built asset map in {} ms: {} ms walking directories, {} ms parsing {} packs, {} ms reading {} loose files, {} ms fetching remote manifests, {} assets",
+        profile.total_ms,
+        profile.directory_walk_ms,
+        profile.pack_parse_ms,
+        profile.packs,
+        profile.loose_file_ms,
+        profile.loose_files,
+        profile.remote_manifest_ms,
+        profile.assets
+    );
+    let tiny_assets = tiny_assets(&asset_map, &config);
+    if !tiny_assets.is_empty() {
+        warn!(
+            "Found {} assets smaller than expected for their file type, which may be corrupt",
+            tiny_assets.len()
+        );
+    }
+
+    let listener = bind_listener(port, &config)?;
+
+    // The Unix socket is only available outside Windows and always serves alongside TCP. The
+    // client only understands HTTP URLs, so ClientConfig.ini keeps pointing at the TCP port, and
+    // sandboxed clients need a bridge from a TCP port inside the sandbox to the socket.
+    let unix_listener = config
+        .unix_socket_path
+        .as_deref()
+        .map(bind_unix_listener)
+        .transpose()?;
+    if let Some(unix_socket_path) = &config.unix_socket_path {
+        info!("Proxy also listening on {}", unix_socket_path.display());
+    }
+    let app = proxy_router(
+        ProxyState {
+            http_client: client_arc,
+            case_insensitive_names: Arc::new(case_insensitive_names(&asset_map, &config)),
+            asset_map: Arc::new(asset_map),
+            game_server_urls: game_server_urls_arc,
+            config: shared_config,
+            compression_cache: Arc::new(Mutex::new(CompressionCache::new(
+                config.compression_cache_size,
+            ))),
+            remote_assets,
+            last_alive,
+        },
+        config.debug_endpoints,
+    );
+    info!(
         "Proxy listening on {}",
         listener.local_addr().expect("Listener has no address")
     );
-    Ok(start_proxy(listener, app))
+    let (ready_sender, ready_receiver) = oneshot::channel();
+    Ok((
+        start_proxy(listener, unix_listener, app, ready_sender),
+        ready_receiver,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::HeaderValue;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+    use tempfile::tempdir;
+    use tokio::time::sleep;
+
+    fn memory_asset(data: &[u8]) -> AssetLocator {
+        AssetLocator {
+            crc: crc32fast::hash(data),
+            kind: AssetLocatorKind::Memory(MemoryAssetLocator {
+                data: data.to_vec(),
+            }),
+            is_override: false,
+            is_compressed: false,
+        }
+    }
+
+    fn test_config() -> ProxyConfig {
+        ProxyConfig {
+            max_asset_path_length: 1024,
+            max_asset_path_components: 32,
+            redirect_policy: ProxyRedirectPolicy::Follow,
+            user_agent: "oxide-client-test".to_string(),
+            listen_backlog: 128,
+            tcp_keepalive: false,
+            debug_endpoints: false,
+            remote_cache_dir: None,
+            asset_override_dir: None,
+            max_remote_asset_size: 1024 * 1024,
+            not_found_message: "Not found".to_string(),
+            unix_socket_path: None,
+            case_insensitive_lookup: false,
+            always_remote: None,
+            strip_name_hash: true,
+            compression_cache_size: 1024 * 1024,
+            remote_headers: HeaderMap::new(),
+            strict_remote_manifests: false,
+            accept_invalid_certs: false,
+            tiny_asset_thresholds: HashMap::new(),
+            max_concurrent_pack_parses: 4,
+        }
+    }
+
+    fn test_state(
+        asset_map: AssetMap,
+        config: ProxyConfig,
+        game_server_urls: Vec<Url>,
+    ) -> ProxyState {
+        ProxyState {
+            http_client: Arc::new(remote_asset_client(&config, &game_server_urls).unwrap()),
+            case_insensitive_names: Arc::new(case_insensitive_names(&asset_map, &config)),
+            asset_map: Arc::new(asset_map),
+            game_server_urls: Arc::new(game_server_urls),
+            compression_cache: Arc::new(Mutex::new(CompressionCache::new(
+                config.compression_cache_size,
+            ))),
+            config: Arc::new(RwLock::new(config)),
+            remote_assets: Arc::new(Mutex::new(BTreeSet::new())),
+            last_alive: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    async fn get_asset(state: &ProxyState, path: &str, headers: HeaderMap) -> Response {
+        let mut request = Request::builder()
+            .uri(format!("/assets/{}", path))
+            .body(Body::empty())
+            .unwrap();
+        *request.headers_mut() = headers;
+        match asset_handler(Path(PathBuf::from(path)), State(state.clone()), request).await {
+            Ok(response) => response,
+            Err(status_code) => status_code.into_response(),
+        }
+    }
+
+    async fn body_bytes(response: Response) -> Vec<u8> {
+        to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    // Serves the router on a random local port in place of a game server
+    async fn mock_server(router: Router) -> Url {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        spawn(async move { serve(listener, router).await.unwrap() });
+        url
+    }
+
+    async fn remote_server(data: &'static [u8]) -> Url {
+        mock_server(Router::new().route("/assets/*asset", get(move || async move { data }))).await
+    }
+
+    // Builds a pack with a single group, optionally starting with the versioned header
+    fn pack_bytes(version: Option<u32>, assets: &[(&[u8], &[u8])]) -> Vec<u8> {
+        let mut pack = Vec::new();
+        if let Some(version) = version {
+            pack.extend_from_slice(PACK_MAGIC);
+            pack.extend_from_slice(&version.to_be_bytes());
+        }
+
+        let table_len: usize = 8 + assets
+            .iter()
+            .map(|(name, _)| 16 + name.len())
+            .sum::<usize>();
+        let mut data_offset = pack.len() + table_len;
+        pack.extend_from_slice(&0u32.to_be_bytes());
+        pack.extend_from_slice(&(assets.len() as u32).to_be_bytes());
+        for (name, data) in assets {
+            pack.extend_from_slice(&(name.len() as u32).to_be_bytes());
+            pack.extend_from_slice(name);
+            pack.extend_from_slice(&(data_offset as u32).to_be_bytes());
+            pack.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            pack.extend_from_slice(&crc32fast::hash(data).to_be_bytes());
+            data_offset += data.len();
+        }
+        for (_, data) in assets {
+            pack.extend_from_slice(data);
+        }
+
+        pack
+    }
+
+    async fn pack_asset_names(pack: &[u8]) -> io::Result<Vec<PathBuf>> {
+        let dir = tempdir().unwrap();
+        let pack_path = dir.path().join("Assets_000.pack");
+        std::fs::write(&pack_path, pack).unwrap();
+        let (_, assets) = list_assets_in_pack(pack_path).await?;
+        Ok(assets.into_iter().map(|asset| asset.name).collect())
+    }
+
+    fn compressed_bytes(data: &[u8]) -> Vec<u8> {
+        let mut compressed = COMPRESSED_MAGIC.to_be_bytes().to_vec();
+        compressed.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        compressed.extend(compress_to_vec_zlib(data, ZLIB_COMPRESSION_LEVEL));
+        compressed
+    }
+
+    async fn failing_server(status_code: StatusCode) -> Url {
+        mock_server(Router::new().route("/assets/*asset", get(move || async move { status_code })))
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_local_asset_not_modified_when_etag_matches() {
+        let asset = memory_asset(b"texture");
+        let etag = asset_etag(asset.crc, false);
+        let state = test_state(
+            HashMap::from([(PathBuf::from("texture.dds"), asset)]),
+            test_config(),
+            Vec::new(),
+        );
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[ETAG], etag.as_str());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_str(&etag).unwrap());
+        let response = get_asset(&state, "texture.dds", headers).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert!(body_bytes(response).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_local_asset_served_when_etag_differs() {
+        let asset = memory_asset(b"texture");
+        let compressed_etag = asset_etag(asset.crc, true);
+        let state = test_state(
+            HashMap::from([(PathBuf::from("texture.dds"), asset)]),
+            test_config(),
+            Vec::new(),
+        );
+
+        // The compressed representation has its own ETag
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            IF_NONE_MATCH,
+            HeaderValue::from_str(&compressed_etag).unwrap(),
+        );
+        let response = get_asset(&state, "texture.dds", headers).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"texture");
+    }
+
+    #[tokio::test]
+    async fn test_remote_asset_ignores_if_none_match() {
+        let state = test_state(
+            HashMap::new(),
+            test_config(),
+            vec![remote_server(b"remote").await],
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(IF_NONE_MATCH, HeaderValue::from_static("*"));
+        let response = get_asset(&state, "texture.dds", headers).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(ETAG).is_none());
+        assert_eq!(body_bytes(response).await, b"remote");
+    }
+
+    #[tokio::test]
+    async fn test_long_asset_path_rejected() {
+        let state = test_state(HashMap::new(), test_config(), Vec::new());
+
+        let long_path = "a".repeat(10_000);
+        let response = get_asset(&state, &long_path, HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::URI_TOO_LONG);
+
+        let deep_path = vec!["a"; 100].join("/");
+        let response = get_asset(&state, &deep_path, HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_asset_path_limits() {
+        let mut config = test_config();
+        config.max_asset_path_length = 10;
+        config.max_asset_path_components = 2;
+        let asset = memory_asset(b"texture");
+        let state = test_state(
+            HashMap::from([(PathBuf::from("a/b.dds"), asset)]),
+            config,
+            Vec::new(),
+        );
+
+        let response = get_asset(&state, "a/b.dds", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get_asset(&state, "abcdef.dds1", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::URI_TOO_LONG);
+        let response = get_asset(&state, "a/b/c", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = get_asset(&state, "../a", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_pack_without_header() {
+        let pack = pack_bytes(None, &[(b"a.dds", b"aaa"), (b"b.dds", b"bb")]);
+        assert_eq!(
+            pack_asset_names(&pack).await.unwrap(),
+            vec![PathBuf::from("a.dds"), PathBuf::from("b.dds")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pack_with_header() {
+        let pack = pack_bytes(Some(1), &[(b"a.dds", b"aaa"), (b"b.dds", b"bb")]);
+        assert_eq!(
+            pack_asset_names(&pack).await.unwrap(),
+            vec![PathBuf::from("a.dds"), PathBuf::from("b.dds")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pack_with_unsupported_version() {
+        let pack = pack_bytes(Some(99), &[(b"a.dds", b"aaa")]);
+        assert_eq!(
+            pack_asset_names(&pack).await.unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pack_assets_served_at_their_offsets() {
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Assets_000.pack"),
+            pack_bytes(Some(1), &[(b"a.dds", b"aaa"), (b"b.dds", b"bb")]),
+        )
+        .unwrap();
+        let (asset_map, _) = build_asset_map(&[dir.path().to_path_buf()], None, &test_config())
+            .await
+            .unwrap();
+        let state = test_state(asset_map, test_config(), Vec::new());
+
+        let response = get_asset(&state, "b.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"bb");
+    }
+
+    #[test]
+    fn test_is_name_hash() {
+        assert!(is_name_hash(OsStr::new("123")));
+        assert!(is_name_hash(OsStr::new("007")));
+        assert!(!is_name_hash(OsStr::new("ui")));
+        assert!(!is_name_hash(OsStr::new("abc")));
+        assert!(!is_name_hash(OsStr::new("1234")));
+        assert!(!is_name_hash(OsStr::new("+12")));
+
+        // One three-byte character is not three digits
+        assert!(!is_name_hash(OsStr::new("€")));
+    }
+
+    #[test]
+    fn test_three_character_folder_not_stripped() {
+        assert_eq!(
+            strip_name_hash(PathBuf::from("abc/texture.dds")),
+            PathBuf::from("abc/texture.dds")
+        );
+        assert_eq!(
+            strip_name_hash(PathBuf::from("€/texture.dds")),
+            PathBuf::from("€/texture.dds")
+        );
+        assert_eq!(
+            strip_name_hash(PathBuf::from("123/texture.dds")),
+            PathBuf::from("texture.dds")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_control_per_branch() {
+        let asset = memory_asset(b"texture");
+        let crc = asset.crc;
+        let mut override_asset = memory_asset(b"override");
+        override_asset.is_override = true;
+        let state = test_state(
+            HashMap::from([
+                (PathBuf::from("texture.dds"), asset),
+                (PathBuf::from("override.dds"), override_asset),
+            ]),
+            test_config(),
+            vec![remote_server(b"remote").await],
+        );
+
+        let cache_control = |response: Response| response.headers()[CACHE_CONTROL].clone();
+
+        let response = get_asset(&state, &format!("texture.dds_{}", crc), HeaderMap::new()).await;
+        assert_eq!(cache_control(response), IMMUTABLE_CACHE_CONTROL);
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(cache_control(response), REVALIDATE_CACHE_CONTROL);
+
+        // An override is served in place of the requested version, so it can't be immutable
+        let response = get_asset(&state, "override.dds_1", HeaderMap::new()).await;
+        assert_eq!(cache_control(response), REVALIDATE_CACHE_CONTROL);
+
+        let response = get_asset(
+            &state,
+            &format!("texture.dds_{}", crc.wrapping_add(1)),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(cache_control(response), NO_STORE_CACHE_CONTROL);
+
+        let response = get_asset(&state, "missing.dds", HeaderMap::new()).await;
+        assert_eq!(cache_control(response), NO_STORE_CACHE_CONTROL);
+    }
+
+    #[test]
+    fn test_decompose_extension_with_separator() {
+        assert_eq!(
+            decompose_extension(std::path::Path::new("Music_1144.snd_stream")),
+            (PathBuf::from("Music_1144.snd_stream"), false, None)
+        );
+        assert_eq!(
+            decompose_extension(std::path::Path::new("Music_1144.snd_stream_42")),
+            (PathBuf::from("Music_1144.snd_stream"), false, Some(42))
+        );
+        assert_eq!(
+            decompose_extension(std::path::Path::new("Music_1144.fsb.z_42")),
+            (PathBuf::from("Music_1144.fsb"), true, Some(42))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_loading_screen_music_resolves_locally() {
+        let music = b"loading screen music 1144";
+        let crc = crc32fast::hash(music);
+        let dir = tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("Assets_000.pack"),
+            pack_bytes(None, &[(b"Music_1144.fsb", music)]),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("Music_1144.snd_stream"), music).unwrap();
+        let (asset_map, _) = build_asset_map(&[dir.path().to_path_buf()], None, &test_config())
+            .await
+            .unwrap();
+
+        // Without game servers, anything that isn't found locally fails
+        let state = test_state(asset_map, test_config(), Vec::new());
+        for path in [
+            format!("Music_1144.fsb_{}", crc),
+            format!("Music_1144.snd_stream_{}", crc),
+        ] {
+            let response = get_asset(&state, &path, HeaderMap::new()).await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", path);
+            assert_eq!(body_bytes(response).await, music, "{}", path);
+        }
+
+        let response = get_asset(
+            &state,
+            &format!("Music_1144.fsb.z_{}", crc),
+            HeaderMap::new(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            decompress_asset_response(body_bytes(response).await).unwrap(),
+            music
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mirror_used_when_primary_unavailable() {
+        let state = test_state(
+            HashMap::new(),
+            test_config(),
+            vec![
+                failing_server(StatusCode::SERVICE_UNAVAILABLE).await,
+                remote_server(b"mirror").await,
+            ],
+        );
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"mirror");
+    }
+
+    #[tokio::test]
+    async fn test_mirror_not_used_when_primary_is_missing_asset() {
+        let state = test_state(
+            HashMap::new(),
+            test_config(),
+            vec![
+                failing_server(StatusCode::NOT_FOUND).await,
+                remote_server(b"mirror").await,
+            ],
+        );
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_empty_assets() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("empty_loose.dds"), b"").unwrap();
+        std::fs::write(
+            dir.path().join("Assets_000.pack"),
+            pack_bytes(None, &[(b"empty_packed.dds", b""), (b"full.dds", b"full")]),
+        )
+        .unwrap();
+        let (asset_map, _) = build_asset_map(&[dir.path().to_path_buf()], None, &test_config())
+            .await
+            .unwrap();
+        let state = test_state(asset_map, test_config(), Vec::new());
+
+        let empty_crc = crc32fast::hash(b"");
+        for asset_name in ["empty_loose.dds", "empty_packed.dds"] {
+            let response = get_asset(
+                &state,
+                &format!("{}_{}", asset_name, empty_crc),
+                HeaderMap::new(),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", asset_name);
+            assert!(body_bytes(response).await.is_empty(), "{}", asset_name);
+
+            let response = get_asset(
+                &state,
+                &format!("{}.z_{}", asset_name, empty_crc),
+                HeaderMap::new(),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK, "{}", asset_name);
+            let compressed = body_bytes(response).await;
+            assert_eq!(
+                compressed[..4],
+                COMPRESSED_MAGIC.to_be_bytes(),
+                "{}",
+                asset_name
+            );
+            assert_eq!(compressed[4..8], 0u32.to_be_bytes(), "{}", asset_name);
+            assert!(
+                decompress_asset_response(compressed).unwrap().is_empty(),
+                "{}",
+                asset_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_decompress_empty_asset_header_only() {
+        let mut header = COMPRESSED_MAGIC.to_be_bytes().to_vec();
+        header.extend_from_slice(&0u32.to_be_bytes());
+        assert!(decompress_asset_response(header).unwrap().is_empty());
+    }
+
+    // Counts requests so that tests can tell whether the server was asked for an asset
+    async fn counting_server(data: &'static [u8]) -> (Url, Arc<AtomicU64>) {
+        let requests = Arc::new(AtomicU64::new(0));
+        let server_requests = Arc::clone(&requests);
+        let url = mock_server(Router::new().route(
+            "/assets/*asset",
+            get(move || async move {
+                server_requests.fetch_add(1, Ordering::SeqCst);
+                data
+            }),
+        ))
+        .await;
+        (url, requests)
+    }
+
+    async fn wait_for_file(path: &std::path::Path) {
+        for _ in 0..100 {
+            if path.exists() {
+                return;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{} was never written", path.display());
+    }
+
+    #[tokio::test]
+    async fn test_remote_asset_cached_on_disk() {
+        let data = b"remote texture";
+        let crc = crc32fast::hash(data);
+        let cache_dir = tempdir().unwrap();
+        let mut config = test_config();
+        config.remote_cache_dir = Some(cache_dir.path().to_path_buf());
+        let (url, requests) = counting_server(data).await;
+        let state = test_state(HashMap::new(), config, vec![url]);
+
+        let asset_path = format!("texture.dds_{}", crc);
+        let response = get_asset(&state, &asset_path, HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, data);
+        wait_for_file(&cache_dir.path().join(&asset_path)).await;
+
+        let response = get_asset(&state, &asset_path, HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, data);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_remote_asset_without_crc_not_cached() {
+        let cache_dir = tempdir().unwrap();
+        let mut config = test_config();
+        config.remote_cache_dir = Some(cache_dir.path().to_path_buf());
+        let (url, requests) = counting_server(b"news").await;
+        let state = test_state(HashMap::new(), config, vec![url]);
+
+        get_asset(&state, "news.txt", HeaderMap::new()).await;
+        get_asset(&state, "news.txt", HeaderMap::new()).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(!cache_dir.path().join("news.txt").exists());
+    }
+
+    #[tokio::test]
+    async fn test_cache_rejects_crc_mismatch() {
+        let cache_dir = tempdir().unwrap();
+        let data = b"texture";
+        let crc = crc32fast::hash(data);
+        let compressed = compressed_bytes(data);
+
+        let mismatched_name = PathBuf::from(format!("texture.dds_{}", crc.wrapping_add(1)));
+        let err = cache_remote_asset(cache_dir.path(), &mismatched_name, data)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(!cache_dir.path().join(&mismatched_name).exists());
+
+        let compressed_name = PathBuf::from(format!("texture.dds.z_{}", crc));
+        cache_remote_asset(cache_dir.path(), &compressed_name, &compressed)
+            .await
+            .unwrap();
+        assert_eq!(
+            std::fs::read(cache_dir.path().join(&compressed_name)).unwrap(),
+            compressed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_override_shadows_pack_and_remote() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_000.pack"),
+            pack_bytes(None, &[(b"texture.dds", b"packed")]),
+        )
+        .unwrap();
+        let override_dir = tempdir().unwrap();
+        std::fs::write(override_dir.path().join("texture.dds"), b"override texture").unwrap();
+        std::fs::write(override_dir.path().join("remote.dds"), b"override remote").unwrap();
+
+        let mut config = test_config();
+        config.asset_override_dir = Some(override_dir.path().to_path_buf());
+        let (asset_map, _) = build_asset_map(&[client_dir.path().to_path_buf()], None, &config)
+            .await
+            .unwrap();
+        let state = test_state(asset_map, config, vec![remote_server(b"remote").await]);
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"override texture".as_slice());
+
+        let response = get_asset(&state, "remote.dds_1", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"override remote".as_slice());
+        assert!(state.remote_assets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_manifest_crc_matches_served_manifest() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_manifest.txt"),
+            "texture.dds,1,10\n",
+        )
+        .unwrap();
+        let remote_manifest = compressed_bytes(b"remote.dds,2,20\n");
+        let server_url = mock_server(Router::new().route(
+            "/assets/*asset",
+            get(move || async move { remote_manifest }),
+        ))
+        .await;
+
+        let config = test_config();
+        let game_server_urls = vec![server_url];
+        let http_client = Client::new();
+        let (asset_map, _) = build_asset_map(
+            &[client_dir.path().to_path_buf()],
+            Some((&http_client, &game_server_urls)),
+            &config,
+        )
+        .await
+        .unwrap();
+        let state = test_state(asset_map, config, game_server_urls);
+
+        let response = get_asset(&state, "manifest.txt", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let manifest = body_bytes(response).await;
+        assert_eq!(manifest, b"texture.dds,1,10\nremote.dds,2,20\n".as_slice());
+        assert!(state.remote_assets.lock().unwrap().is_empty());
+
+        let response = get_asset(&state, "manifest.crc", HeaderMap::new()).await;
+        assert_eq!(
+            body_bytes(response).await,
+            crc32fast::hash(&manifest).to_string().as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_asset_precedence() {
+        let client_dir = tempdir().unwrap();
+        // Written out of order so that the result doesn't depend on creation order
+        std::fs::write(
+            client_dir.path().join("Assets_001.pack"),
+            pack_bytes(
+                None,
+                &[(b"texture.dds", b"second"), (b"model.adr", b"second")],
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_000.pack"),
+            pack_bytes(
+                None,
+                &[(b"texture.dds", b"first"), (b"model.adr", b"first")],
+            ),
+        )
+        .unwrap();
+        std::fs::write(client_dir.path().join("model.adr"), b"loose").unwrap();
+
+        let (asset_map, _) =
+            build_asset_map(&[client_dir.path().to_path_buf()], None, &test_config())
+                .await
+                .unwrap();
+        let state = test_state(asset_map, test_config(), Vec::new());
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"first".as_slice());
+        let response = get_asset(&state, "model.adr", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"loose".as_slice());
+    }
+
+    #[test]
+    fn test_normalize_pack_asset_name() {
+        let expected: PathBuf = ["Textures", "Foo.dds"].iter().collect();
+        assert_eq!(normalize_pack_asset_name("Textures\\Foo.dds"), expected);
+        assert_eq!(normalize_pack_asset_name("Textures/Foo.dds"), expected);
+        assert_eq!(normalize_pack_asset_name("/Textures//Foo.dds"), expected);
+    }
+
+    #[tokio::test]
+    async fn test_pack_assets_with_separators_resolve_locally() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_000.pack"),
+            pack_bytes(
+                None,
+                &[
+                    (b"Textures\\Foo.dds", b"backslash"),
+                    (b"Models/Bar.adr", b"slash"),
+                ],
+            ),
+        )
+        .unwrap();
+
+        let mut config = test_config();
+        config.case_insensitive_lookup = true;
+        let (asset_map, _) = build_asset_map(&[client_dir.path().to_path_buf()], None, &config)
+            .await
+            .unwrap();
+        let state = test_state(asset_map, config, vec![remote_server(b"remote").await]);
+
+        let response = get_asset(&state, "Textures/Foo.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"backslash".as_slice());
+        let response = get_asset(&state, "textures/foo.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"backslash".as_slice());
+        let response = get_asset(&state, "MODELS/bar.ADR", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"slash".as_slice());
+        assert!(state.remote_assets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_case_insensitive_lookup() {
+        let asset_map = HashMap::from([(
+            ["textures", "foo.dds"].iter().collect::<PathBuf>(),
+            memory_asset(b"local"),
+        )]);
+        let mut config = test_config();
+        config.case_insensitive_lookup = true;
+        let state = test_state(asset_map, config, vec![remote_server(b"remote").await]);
+
+        let response = get_asset(&state, "Textures/Foo.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"local".as_slice());
+        assert!(state.remote_assets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_case_sensitive_lookup() {
+        let asset_map = HashMap::from([(
+            ["textures", "foo.dds"].iter().collect::<PathBuf>(),
+            memory_asset(b"local"),
+        )]);
+        let state = test_state(
+            asset_map,
+            test_config(),
+            vec![remote_server(b"remote").await],
+        );
+
+        let response = get_asset(&state, "Textures/Foo.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"remote".as_slice());
+    }
+
+    #[test]
+    fn test_case_insensitive_names_pick_stable_name() {
+        let asset_map = HashMap::from([
+            (PathBuf::from("Foo.dds"), memory_asset(b"upper")),
+            (PathBuf::from("foo.dds"), memory_asset(b"lower")),
+        ]);
+        let mut config = test_config();
+        config.case_insensitive_lookup = true;
+        let names = case_insensitive_names(&asset_map, &config);
+        assert_eq!(
+            names[&lowercase_asset_name(&PathBuf::from("FOO.DDS"))],
+            PathBuf::from("Foo.dds")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pack_skips_invalid_utf8_name() {
+        let pack = pack_bytes(
+            None,
+            &[
+                (b"first.dds", b"first"),
+                (b"bad\xff\xfe.dds", b"bad"),
+                (b"second.dds", b"second"),
+            ],
+        );
+        assert_eq!(
+            pack_asset_names(&pack).await.unwrap(),
+            vec![PathBuf::from("first.dds"), PathBuf::from("second.dds")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_asset_request_without_prefix() {
+        let state = test_state(
+            HashMap::new(),
+            test_config(),
+            vec![remote_server(b"remote").await],
+        );
+        let request = Request::builder()
+            .uri("/texture.dds")
+            .body(Body::empty())
+            .unwrap();
+        let result = asset_handler(Path(PathBuf::from("texture.dds")), State(state), request).await;
+        assert_eq!(result.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_pre_compressed_loose_file() {
+        let data = b"raw texture";
+        let compressed = compressed_bytes(data);
+        let client_dir = tempdir().unwrap();
+        std::fs::write(client_dir.path().join("texture.dds"), &compressed).unwrap();
+
+        let (asset_map, _) =
+            build_asset_map(&[client_dir.path().to_path_buf()], None, &test_config())
+                .await
+                .unwrap();
+        let state = test_state(
+            asset_map,
+            test_config(),
+            vec![remote_server(b"remote").await],
+        );
+
+        // The CRC is of the uncompressed asset, like it is for every other asset
+        let crc = crc32fast::hash(data);
+        let response = get_asset(&state, &format!("texture.dds_{}", crc), HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, data.as_slice());
+
+        // The stored file is served as-is rather than compressed a second time
+        let response = get_asset(&state, &format!("texture.dds.z_{}", crc), HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, compressed);
+        assert!(state.remote_assets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_always_remote_skips_local_asset() {
+        let asset_map = HashMap::from([
+            (PathBuf::from("news.txt"), memory_asset(b"local news")),
+            (PathBuf::from("texture.dds"), memory_asset(b"local texture")),
+        ]);
+        let mut config = test_config();
+        config.always_remote = always_remote_regex(&["*.txt".to_string()]).unwrap();
+        let state = test_state(asset_map, config, vec![remote_server(b"remote").await]);
+
+        let response = get_asset(&state, "news.txt", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"remote".as_slice());
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"local texture".as_slice());
+    }
+
+    #[test]
+    fn test_requested_asset_name_strip_enabled() {
+        let config = test_config();
+        assert_eq!(
+            requested_asset_name(PathBuf::from("123/texture.dds"), &config),
+            PathBuf::from("texture.dds")
+        );
+        assert_eq!(
+            requested_asset_name(PathBuf::from("abc/texture.dds"), &config),
+            PathBuf::from("abc/texture.dds")
+        );
+    }
+
+    #[test]
+    fn test_requested_asset_name_strip_disabled() {
+        let mut config = test_config();
+        config.strip_name_hash = false;
+        assert_eq!(
+            requested_asset_name(PathBuf::from("123/texture.dds"), &config),
+            PathBuf::from("123/texture.dds")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_numeric_folder_served_when_strip_disabled() {
+        let asset_map = HashMap::from([(
+            ["123", "texture.dds"].iter().collect::<PathBuf>(),
+            memory_asset(b"in folder"),
+        )]);
+        let mut config = test_config();
+        config.strip_name_hash = false;
+        let state = test_state(asset_map, config, vec![remote_server(b"remote").await]);
+
+        let response = get_asset(&state, "123/texture.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"in folder".as_slice());
+    }
+
+    fn cache_key(name: &str) -> (PathBuf, u32) {
+        (PathBuf::from(name), 1)
+    }
+
+    #[test]
+    fn test_compression_cache_evicts_least_recently_used() {
+        let mut cache = CompressionCache::new(8);
+        cache.insert(cache_key("first.dds"), Bytes::from_static(b"1111"));
+        cache.insert(cache_key("second.dds"), Bytes::from_static(b"2222"));
+
+        // Using the first entry makes the second one the least recently used
+        assert!(cache.get(&cache_key("first.dds")).is_some());
+        cache.insert(cache_key("third.dds"), Bytes::from_static(b"3333"));
+
+        assert!(cache.get(&cache_key("second.dds")).is_none());
+        assert_eq!(
+            cache.get(&cache_key("first.dds")),
+            Some(Bytes::from_static(b"1111"))
+        );
+        assert_eq!(
+            cache.get(&cache_key("third.dds")),
+            Some(Bytes::from_static(b"3333"))
+        );
+        assert_eq!(cache.size, 8);
+        assert_eq!(cache.order.len(), cache.entries.len());
+    }
+
+    #[test]
+    fn test_compression_cache_skips_oversized_entry() {
+        let mut cache = CompressionCache::new(4);
+        cache.insert(cache_key("small.dds"), Bytes::from_static(b"1111"));
+        cache.insert(cache_key("large.dds"), Bytes::from_static(b"22222"));
+
+        assert!(cache.get(&cache_key("large.dds")).is_none());
+        assert!(cache.get(&cache_key("small.dds")).is_some());
+    }
+
+    #[test]
+    fn test_compression_cache_keyed_by_crc() {
+        let mut cache = CompressionCache::new(8);
+        cache.insert(
+            (PathBuf::from("texture.dds"), 1),
+            Bytes::from_static(b"old"),
+        );
+        assert!(cache.get(&(PathBuf::from("texture.dds"), 2)).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_remote_headers_sent() {
+        let server_url = mock_server(Router::new().route(
+            "/assets/*asset",
+            get(|headers: HeaderMap| async move {
+                match headers.get("x-api-key") {
+                    Some(api_key) => (StatusCode::OK, api_key.as_bytes().to_vec()),
+                    None => (StatusCode::UNAUTHORIZED, Vec::new()),
+                }
+            }),
+        ))
+        .await;
+        let mut config = test_config();
+        config
+            .remote_headers
+            .insert("x-api-key", HeaderValue::from_static("secret"));
+        let state = test_state(HashMap::new(), config, vec![server_url]);
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"secret".as_slice());
+    }
+
+    async fn build_with_missing_remote_manifest(
+        strict_remote_manifests: bool,
+    ) -> io::Result<AssetMap> {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_manifest.txt"),
+            "texture.dds,1,10\n",
+        )
+        .unwrap();
+        let mut config = test_config();
+        config.strict_remote_manifests = strict_remote_manifests;
+        let game_server_urls = vec![failing_server(StatusCode::NOT_FOUND).await];
+        let http_client = remote_asset_client(&config, &game_server_urls).unwrap();
+        build_asset_map(
+            &[client_dir.path().to_path_buf()],
+            Some((&http_client, &game_server_urls)),
+            &config,
+        )
+        .await
+        .map(|(asset_map, _)| asset_map)
+    }
+
+    #[tokio::test]
+    async fn test_missing_remote_manifest_uses_local_manifest() {
+        let asset_map = build_with_missing_remote_manifest(false).await.unwrap();
+        let manifest = &asset_map[&PathBuf::from("manifest.txt")];
+        let AssetLocatorKind::Memory(locator) = &manifest.kind else {
+            panic!("Merged manifest should be stored in memory");
+        };
+        assert_eq!(locator.data, b"texture.dds,1,10\n");
+        assert_eq!(manifest.crc, crc32fast::hash(b"texture.dds,1,10\n"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_remote_manifest_fails_strict_build() {
+        let Err(err) = build_with_missing_remote_manifest(true).await else {
+            panic!("Strict build should fail when the remote manifest is missing");
+        };
+        assert!(err.to_string().contains("404"));
+    }
+
+    #[tokio::test]
+    async fn test_generated_manifest_crc_beats_loose_file() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(
+            client_dir.path().join("Assets_manifest.txt"),
+            "texture.dds,1,10\n",
+        )
+        .unwrap();
+        std::fs::write(client_dir.path().join("manifest.crc"), "12345").unwrap();
+        let override_dir = tempdir().unwrap();
+        std::fs::write(override_dir.path().join("manifest.crc"), "67890").unwrap();
+
+        let mut config = test_config();
+        config.asset_override_dir = Some(override_dir.path().to_path_buf());
+        let (asset_map, _) = build_asset_map(&[client_dir.path().to_path_buf()], None, &config)
+            .await
+            .unwrap();
+        let state = test_state(asset_map, config, Vec::new());
+
+        let response = get_asset(&state, "manifest.crc", HeaderMap::new()).await;
+        assert_eq!(
+            body_bytes(response).await,
+            crc32fast::hash(b"texture.dds,1,10\n")
+                .to_string()
+                .as_bytes()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_retries_transient_error() {
+        let mut attempts = 0;
+        let result = read_with_retries(std::path::Path::new("test.dds"), || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt == 1 {
+                    Err(io::Error::from(ErrorKind::PermissionDenied))
+                } else {
+                    Ok(vec![1, 2, 3])
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), vec![1, 2, 3]);
+        assert_eq!(attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_read_does_not_retry_other_errors() {
+        let mut attempts = 0;
+        let result = read_with_retries(std::path::Path::new("test.dds"), || {
+            attempts += 1;
+            async { Err(io::Error::from(ErrorKind::NotFound)) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::NotFound);
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_gives_up_after_retries() {
+        let mut attempts = 0;
+        let result = read_with_retries(std::path::Path::new("test.dds"), || {
+            attempts += 1;
+            async { Err(io::Error::from(ErrorKind::PermissionDenied)) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(attempts, FILE_READ_RETRIES + 1);
+    }
+
+    #[tokio::test]
+    async fn test_asset_root_not_found() {
+        let (remote_url, requests) = counting_server(b"remote").await;
+        let state = test_state(HashMap::new(), test_config(), vec![remote_url]);
+        let proxy_url = mock_server(proxy_router(state, false)).await;
+
+        for path in ["assets", "assets/"] {
+            let response = reqwest::get(proxy_url.join(path).unwrap()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tiny_assets_flagged() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(client_dir.path().join("broken.dds"), [0; 4]).unwrap();
+        std::fs::write(client_dir.path().join("SHOUTING.DDS"), [0; 8]).unwrap();
+        std::fs::write(client_dir.path().join("fine.dds"), [0; 200]).unwrap();
+        std::fs::write(client_dir.path().join("note.txt"), [0; 1]).unwrap();
+        let mut config = test_config();
+        config.tiny_asset_thresholds = HashMap::from([("dds".to_string(), 128)]);
+
+        let tiny_assets = find_client_tiny_assets(&[client_dir.path().to_path_buf()], &config)
+            .await
+            .unwrap();
+        let flagged: Vec<(PathBuf, u64)> = tiny_assets
+            .into_iter()
+            .map(|tiny_asset| (tiny_asset.name, tiny_asset.size))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (PathBuf::from("SHOUTING.DDS"), 8),
+                (PathBuf::from("broken.dds"), 4)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_many_packs_with_one_parse_permit() {
+        let client_dir = tempdir().unwrap();
+        for index in 0..64 {
+            let unique_name = format!("asset_{}.dds", index);
+            let data = format!("pack {}", index);
+            std::fs::write(
+                client_dir.path().join(format!("Assets_{:03}.pack", index)),
+                pack_bytes(
+                    None,
+                    &[
+                        (unique_name.as_bytes(), data.as_bytes()),
+                        (b"shared.dds", data.as_bytes()),
+                    ],
+                ),
+            )
+            .unwrap();
+        }
+        let mut config = test_config();
+        config.max_concurrent_pack_parses = 1;
+
+        let (asset_map, _) = build_asset_map(&[client_dir.path().to_path_buf()], None, &config)
+            .await
+            .unwrap();
+        assert_eq!(asset_map.len(), 65);
+        let state = test_state(asset_map, config, Vec::new());
+
+        let response = get_asset(&state, "asset_63.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"pack 63".as_slice());
+        let response = get_asset(&state, "shared.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"pack 0".as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_later_layer_overrides_earlier_layer() {
+        let base_dir = tempdir().unwrap();
+        std::fs::write(base_dir.path().join("texture.dds"), b"base loose").unwrap();
+        std::fs::write(base_dir.path().join("base_only.dds"), b"base only").unwrap();
+        let update_dir = tempdir().unwrap();
+        std::fs::write(
+            update_dir.path().join("Assets_000.pack"),
+            pack_bytes(None, &[(b"texture.dds", b"update packed")]),
+        )
+        .unwrap();
+
+        let client_folders = [
+            base_dir.path().to_path_buf(),
+            update_dir.path().to_path_buf(),
+        ];
+        let (asset_map, _) = build_asset_map(&client_folders, None, &test_config())
+            .await
+            .unwrap();
+        let state = test_state(asset_map, test_config(), Vec::new());
+
+        let response = get_asset(&state, "texture.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"update packed".as_slice());
+        let response = get_asset(&state, "base_only.dds", HeaderMap::new()).await;
+        assert_eq!(body_bytes(response).await, b"base only".as_slice());
+    }
+
+    #[tokio::test]
+    async fn test_single_layer() {
+        let client_dir = tempdir().unwrap();
+        std::fs::write(client_dir.path().join("texture.dds"), b"texture").unwrap();
+
+        let (asset_map, _) =
+            build_asset_map(&[client_dir.path().to_path_buf()], None, &test_config())
+                .await
+                .unwrap();
+        assert_eq!(
+            asset_map.keys().collect::<Vec<_>>(),
+            vec![&PathBuf::from("texture.dds")]
+        );
+    }
 }
@@ -1,36 +1,105 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 use std::fmt::Display;
-use std::fs::{copy, create_dir_all, read, read_dir, write};
+use std::fs::{
+    copy, create_dir_all, metadata, read, read_dir, remove_dir_all, remove_file, write, File,
+};
+use std::future::Future;
 use std::io;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::string::ToString;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use ini::Ini;
+use pelite::PeFile;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
 use regex::bytes::Regex;
-use reqwest::Url;
-use serde::{Deserialize, Serialize};
-use tauri::{Manager, State};
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, RANGE,
+    USER_AGENT,
+};
+use reqwest::{Client, Response, StatusCode, Url};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use sysinfo::{Disks, Pid, System, MINIMUM_CPU_UPDATE_INTERVAL};
+use tauri::api::shell;
+use tauri::{AppHandle, Manager, State, SystemTray, SystemTrayEvent};
 use tokio::spawn;
-use tokio::task::{spawn_blocking, JoinHandle};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::{spawn_blocking, AbortHandle, JoinHandle, JoinSet};
+use tokio::time::{sleep, timeout};
+use tracing::{error, info, warn, Level};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
 
-use crate::http_proxy::prepare_proxy;
+use crate::http_proxy::{
+    always_remote_regex, check_client_packs, fetch_remote_asset, find_client_assets_by_crc,
+    find_client_tiny_assets, missing_manifest_assets, prepare_proxy, profile_client_asset_map,
+    remote_asset_client, resolve_client_asset, AssetMapProfile, AssetResolution, PackStatus,
+    ProxyConfig, ProxyRedirectPolicy, TinyAsset,
+};
 
 mod http_proxy;
 
 const SAVED_SERVERS_PATH: &str = "saved-servers.json";
+const SAVED_SERVERS_BACKUP_EXTENSION: &str = "json.bak";
 const USER_SETTINGS_PATH: &str = "settings.json";
 const I18N_GLOBAL_CONFIG_PATH: &str = "i18n.json";
+const EMBEDDED_I18N_GLOBAL_CONFIG: &str = include_str!("../i18n.json");
 const DEFAULT_LANGUAGE_ID: &str = "en-US";
 const LANGUAGE_NAME_KEY: &str = "name";
+const PROXY_NOT_FOUND_I18N_KEY: &str = "proxy-not-found";
 const USER_OPTIONS_TEMPLATE_PATH: &str = "user-options-template.ini";
 const CLIENT_CONFIG_PATH: &str = "ClientConfig.ini";
 const USER_OPTIONS_PATH: &str = "UserOptions.ini";
 const ACTIVE_CLIENT_EXECUTABLE: &str = "CloneWars.exe";
+const MAIN_WINDOW_LABEL: &str = "main";
+const DEFAULT_USER_AGENT: &str = concat!("oxide-client/", env!("CARGO_PKG_VERSION"));
+const UPDATE_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+const PROXY_READY_TIMEOUT: Duration = Duration::from_secs(5);
+const LOG_FILE_PREFIX: &str = "oxide-client";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+const MAX_CLIENT_FILE_SIZE: u64 = 256 * 1024 * 1024;
+const PROXY_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_PROXY_RESTARTS: u32 = 3;
+const PROXY_IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const PROXY_CONNECTIVITY_TIMEOUT: Duration = Duration::from_secs(3);
+const PROXY_CONNECTIVITY_ASSET: &str = "manifest.crc";
+const SERVER_PING_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_CONCURRENT_SERVER_PINGS: usize = 8;
+const DEFAULT_MAX_CONCURRENT_PACK_PARSES: usize = 8;
+const MAX_CONCURRENT_PREFETCHES: usize = 8;
+const DEFAULT_GUID: &str = "1";
+const DEFAULT_TICKET: &str = "p7w9dGPBPbbm9ZG";
+const DEFAULT_LOCALE: u32 = 8;
+const DEFAULT_LOADING_SCREEN_ID: i32 = -1;
+const DEFAULT_LOADING_SCREEN_MUSIC_ID: u32 = 1144;
+const DEFAULT_RANDOM_TICKET_CHARSET: &str =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+const LOG_LINE_EVENT: &str = "log-line";
+const CLIENT_LOG_FILE_NAME: &str = "client.log";
+const CLIENT_LOG_EVENT: &str = "client-log";
+const INSTALL_PROGRESS_EVENT: &str = "install-progress";
+const MAX_CLIENT_LOG_LINE_LENGTH: u64 = 4096;
+const MAX_TAIL_LOG_LINES: usize = 1000;
+const MAX_TAIL_LOG_BYTES: u64 = 1024 * 1024;
+const TAIL_LOG_CHUNK_SIZE: u64 = 64 * 1024;
+const MAX_NICKNAME_LENGTH: usize = 64;
+const MAX_STORAGE_SCAN_FILES: usize = 1_000_000;
 
 struct GlobalState {
     settings_path: PathBuf,
@@ -38,9 +107,57 @@ struct GlobalState {
     saved_servers: Mutex<VecDeque<SavedServer>>,
     languages: HashMap<String, Language>,
     settings: Mutex<Settings>,
-    active_client_path: PathBuf,
-    user_options_template_path: PathBuf,
+    active_client_path: Mutex<PathBuf>,
+    default_active_client_path: PathBuf,
+    remote_cache_path: PathBuf,
+    log_dir: PathBuf,
+    user_options_template_path: Option<PathBuf>,
     proxy_process: tokio::sync::Mutex<Option<(JoinHandle<()>, JoinHandle<()>)>>,
+    http_client: Client,
+    client_pid: Arc<Mutex<Option<u32>>>,
+    remote_assets: Arc<Mutex<BTreeSet<String>>>,
+    launch_cancelled: AtomicBool,
+    launch_in_progress: Arc<AtomicBool>,
+    launch_abort_handle: Mutex<Option<AbortHandle>>,
+    live_proxy_config: Mutex<Option<Arc<RwLock<ProxyConfig>>>>,
+    session_ticket: Mutex<Option<String>>,
+    active_proxy_port: Arc<Mutex<Option<u16>>>,
+    proxy_session: Mutex<Option<ProxySession>>,
+}
+
+// A proxy that is still running can only serve a new launch of the same client and server
+#[derive(PartialEq)]
+struct ProxySession {
+    client_folders: Vec<PathBuf>,
+    server_identity: SavedServerIdentity,
+    proxy_port: u16,
+}
+
+// Clears the launch flag however start_client returns
+struct LaunchGuard<'a>(&'a AtomicBool);
+
+impl<'a> LaunchGuard<'a> {
+    fn acquire(launch_in_progress: &'a AtomicBool) -> Result<Self, String> {
+        launch_in_progress
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .map(|_| LaunchGuard(launch_in_progress))
+            .map_err(|_| "Launch already in progress".to_string())
+    }
+}
+
+impl Drop for LaunchGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+impl GlobalState {
+    fn active_client_path(&self) -> PathBuf {
+        self.active_client_path
+            .lock()
+            .expect("Unable to lock active client path")
+            .clone()
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
@@ -48,13 +165,443 @@ struct SavedServer {
     nickname: String,
     udp_endpoint: String,
     https_endpoint: String,
+    #[serde(default)]
+    mirror_https_endpoints: Vec<String>,
+    #[serde(default = "default_guid")]
+    guid: String,
+    #[serde(default = "default_ticket")]
+    ticket: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    website: Option<String>,
+    #[serde(default)]
+    accept_invalid_certs: bool,
+    #[serde(default)]
+    launch_overrides: LaunchOverrides,
+}
+
+// Launch arguments that users can change per server. Unset fields use the defaults.
+#[derive(Clone, Default, Deserialize, Serialize)]
+struct LaunchOverrides {
+    locale: Option<u32>,
+    loading_screen_id: Option<i32>,
+    live_gamer: Option<bool>,
+    #[serde(default)]
+    extra: Vec<String>,
+}
+
+struct LaunchArgs {
+    // UDP endpoint of the game server
+    server: String,
+    guid: String,
+    ticket: String,
+
+    // Internationalization:Locale, which is 8 for English
+    locale: u32,
+
+    // LoadingScreenId, where -1 is the default
+    loading_screen_id: i32,
+    live_gamer: bool,
+
+    // Passed through as-is after all the other arguments
+    extra: Vec<String>,
+}
+
+impl LaunchArgs {
+    fn new(saved_server: &SavedServer, ticket: String) -> Self {
+        let overrides = &saved_server.launch_overrides;
+        LaunchArgs {
+            server: saved_server.udp_endpoint.clone(),
+            guid: saved_server.guid.clone(),
+            ticket,
+            locale: overrides.locale.unwrap_or(DEFAULT_LOCALE),
+            loading_screen_id: overrides
+                .loading_screen_id
+                .unwrap_or(DEFAULT_LOADING_SCREEN_ID),
+            live_gamer: overrides.live_gamer.unwrap_or(true),
+            extra: overrides.extra.clone(),
+        }
+    }
+
+    // Keep the order the launcher has always used in case the client depends on it
+    fn render(&self) -> Vec<String> {
+        let mut args = vec![
+            format!("inifile={}", CLIENT_CONFIG_PATH),
+            format!("Guid={}", self.guid),
+            format!("Server={}", self.server),
+            format!("Ticket={}", self.ticket),
+            format!("Internationalization:Locale={}", self.locale),
+            format!("LoadingScreenId={}", self.loading_screen_id),
+            format!("LiveGamer={}", u8::from(self.live_gamer)),
+        ];
+        args.extend(self.extra.iter().cloned());
+        args
+    }
+}
+
+fn default_guid() -> String {
+    DEFAULT_GUID.to_string()
+}
+
+fn default_ticket() -> String {
+    DEFAULT_TICKET.to_string()
+}
+
+#[derive(Clone, Deserialize, Serialize, PartialEq)]
+struct SavedServerIdentity {
+    udp_endpoint: String,
+    https_endpoint: String,
+}
+
+impl From<&SavedServer> for SavedServerIdentity {
+    fn from(saved_server: &SavedServer) -> Self {
+        SavedServerIdentity {
+            udp_endpoint: saved_server.udp_endpoint.clone(),
+            https_endpoint: saved_server.https_endpoint.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+struct SavedClient {
+    version: String,
+    path: PathBuf,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    last_used_server: Option<SavedServerIdentity>,
+    #[serde(default)]
+    sha256: Option<String>,
+
+    // Folders such as update packs whose assets are served over the client's own assets
+    #[serde(default)]
+    layers: Vec<PathBuf>,
 }
 
 #[derive(Deserialize, Serialize)]
+#[serde(default)]
 struct Settings {
-    clients: HashMap<String, PathBuf>,
+    #[serde(deserialize_with = "deserialize_clients")]
+    clients: Vec<SavedClient>,
+    language: String,
+    proxy_port: u16,
+    proxy_max_asset_path_length: usize,
+    proxy_max_asset_path_components: usize,
+    update_url: String,
+    proxy_redirect_policy: ProxyRedirectPolicy,
+    user_agent: Option<String>,
+    proxy_listen_backlog: u32,
+    proxy_tcp_keepalive: bool,
+    proxy_debug_endpoints: bool,
+    client_env: HashMap<String, String>,
+    asset_override_dir: Option<PathBuf>,
+    proxy_max_remote_asset_size: u64,
+    trusted_client_hashes: Vec<String>,
+    active_client_dir: Option<PathBuf>,
+    proxy_unix_socket_path: Option<PathBuf>,
+    proxy_case_insensitive_lookup: bool,
+    on_launch_behavior: OnLaunchBehavior,
+    proxy_always_remote_patterns: Vec<String>,
+    proxy_strip_name_hash: bool,
+    proxy_compression_cache_size: u64,
+    generate_random_ticket: bool,
+    random_ticket_length: usize,
+    random_ticket_charset: String,
+    proxy_strict_remote_manifests: bool,
+    direct_connect: bool,
+    proxy_idle_shutdown_secs: u64,
+    tiny_asset_thresholds: HashMap<String, u64>,
+    proxy_max_concurrent_pack_parses: usize,
+    loading_screen_music_id: u32,
+}
+
+// Parsing is mostly waiting on disk reads, so a few tasks per core keeps the disk busy without
+// opening every pack at once
+fn default_max_concurrent_pack_parses() -> usize {
+    thread::available_parallelism()
+        .map(|parallelism| parallelism.get() * 2)
+        .unwrap_or(DEFAULT_MAX_CONCURRENT_PACK_PARSES)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            clients: Vec::new(),
+            language: DEFAULT_LANGUAGE_ID.to_string(),
+            proxy_port: 4001,
+            proxy_max_asset_path_length: 1024,
+            proxy_max_asset_path_components: 32,
+            update_url: "https://api.github.com/repos/soir20/oxide-client/releases/latest"
+                .to_string(),
+            proxy_redirect_policy: ProxyRedirectPolicy::SameHost,
+            user_agent: None,
+            proxy_listen_backlog: 1024,
+            proxy_tcp_keepalive: true,
+            proxy_debug_endpoints: false,
+            client_env: HashMap::new(),
+            asset_override_dir: None,
+            proxy_max_remote_asset_size: 256 * 1024 * 1024,
+            trusted_client_hashes: Vec::new(),
+            active_client_dir: None,
+            proxy_unix_socket_path: None,
+            proxy_case_insensitive_lookup: true,
+            on_launch_behavior: OnLaunchBehavior::Stay,
+            proxy_always_remote_patterns: Vec::new(),
+            proxy_strip_name_hash: true,
+            proxy_compression_cache_size: 64 * 1024 * 1024,
+            generate_random_ticket: false,
+            random_ticket_length: DEFAULT_TICKET.len(),
+            random_ticket_charset: DEFAULT_RANDOM_TICKET_CHARSET.to_string(),
+            proxy_strict_remote_manifests: false,
+            direct_connect: false,
+            proxy_idle_shutdown_secs: 120,
+            tiny_asset_thresholds: HashMap::new(),
+            proxy_max_concurrent_pack_parses: default_max_concurrent_pack_parses(),
+            loading_screen_music_id: DEFAULT_LOADING_SCREEN_MUSIC_ID,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct SettingsDto {
     language: String,
     proxy_port: u16,
+    proxy_max_asset_path_length: usize,
+    proxy_max_asset_path_components: usize,
+    update_url: String,
+    proxy_redirect_policy: ProxyRedirectPolicy,
+    user_agent: Option<String>,
+    proxy_listen_backlog: u32,
+    proxy_tcp_keepalive: bool,
+    proxy_debug_endpoints: bool,
+    client_env: HashMap<String, String>,
+    asset_override_dir: Option<PathBuf>,
+    proxy_max_remote_asset_size: u64,
+    trusted_client_hashes: Vec<String>,
+    active_client_dir: Option<PathBuf>,
+    proxy_unix_socket_path: Option<PathBuf>,
+    proxy_case_insensitive_lookup: bool,
+    on_launch_behavior: OnLaunchBehavior,
+    proxy_always_remote_patterns: Vec<String>,
+    proxy_strip_name_hash: bool,
+    proxy_compression_cache_size: u64,
+    generate_random_ticket: bool,
+    random_ticket_length: usize,
+    random_ticket_charset: String,
+    proxy_strict_remote_manifests: bool,
+    direct_connect: bool,
+    proxy_idle_shutdown_secs: u64,
+    tiny_asset_thresholds: HashMap<String, u64>,
+    proxy_max_concurrent_pack_parses: usize,
+    loading_screen_music_id: u32,
+}
+
+#[derive(Deserialize)]
+struct SettingsUpdate {
+    language: Option<String>,
+    proxy_port: Option<u16>,
+    proxy_max_asset_path_length: Option<usize>,
+    proxy_max_asset_path_components: Option<usize>,
+    update_url: Option<String>,
+    proxy_redirect_policy: Option<ProxyRedirectPolicy>,
+    user_agent: Option<String>,
+    proxy_listen_backlog: Option<u32>,
+    proxy_tcp_keepalive: Option<bool>,
+    proxy_debug_endpoints: Option<bool>,
+    client_env: Option<HashMap<String, String>>,
+    asset_override_dir: Option<PathBuf>,
+    proxy_max_remote_asset_size: Option<u64>,
+    trusted_client_hashes: Option<Vec<String>>,
+    proxy_unix_socket_path: Option<PathBuf>,
+    proxy_case_insensitive_lookup: Option<bool>,
+    on_launch_behavior: Option<OnLaunchBehavior>,
+    proxy_always_remote_patterns: Option<Vec<String>>,
+    proxy_strip_name_hash: Option<bool>,
+    proxy_compression_cache_size: Option<u64>,
+    generate_random_ticket: Option<bool>,
+    random_ticket_length: Option<usize>,
+    random_ticket_charset: Option<String>,
+    proxy_strict_remote_manifests: Option<bool>,
+    direct_connect: Option<bool>,
+    proxy_idle_shutdown_secs: Option<u64>,
+    tiny_asset_thresholds: Option<HashMap<String, u64>>,
+    proxy_max_concurrent_pack_parses: Option<usize>,
+    loading_screen_music_id: Option<u32>,
+}
+
+// Only includes the proxy settings that a running proxy picks up without a relaunch
+#[derive(Deserialize)]
+struct ProxyConfigUpdate {
+    proxy_max_asset_path_length: Option<usize>,
+    proxy_max_asset_path_components: Option<usize>,
+    proxy_max_remote_asset_size: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct StorageReport {
+    active_client: u64,
+    remote_cache: u64,
+    logs: u64,
+    total: u64,
+    truncated: bool,
+}
+
+#[derive(Serialize)]
+struct ResourceUsage {
+    cpu_percent: f32,
+    memory_bytes: u64,
+}
+
+#[derive(Serialize)]
+struct ClientIntegrity {
+    sha256: String,
+    matches_saved_hash: Option<bool>,
+    trusted: Option<bool>,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum OnLaunchBehavior {
+    Stay,
+    Minimize,
+    Tray,
+    Exit,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum CopyExclusionReason {
+    Executable,
+    ClientConfig,
+    UserOptions,
+    WebDownloadedPack,
+}
+
+#[derive(Serialize)]
+struct CopyExclusion {
+    path: PathBuf,
+    reason: CopyExclusionReason,
+}
+
+#[derive(Serialize)]
+struct CopyPlan {
+    included: Vec<PathBuf>,
+    excluded: Vec<CopyExclusion>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+enum ClientConfigChangeKind {
+    Added {
+        new_value: String,
+    },
+    Changed {
+        old_value: String,
+        new_value: String,
+    },
+    Removed {
+        old_value: String,
+    },
+}
+
+#[derive(Serialize)]
+struct ClientConfigChange {
+    section: Option<String>,
+    key: String,
+    #[serde(flatten)]
+    kind: ClientConfigChangeKind,
+}
+
+#[derive(Serialize)]
+struct RepairReport {
+    kept: usize,
+    dropped: usize,
+    backup_path: PathBuf,
+}
+
+#[derive(Serialize)]
+struct PrefetchFailure {
+    asset_path: String,
+    error: String,
+}
+
+#[derive(Serialize)]
+struct PrefetchReport {
+    fetched: Vec<String>,
+    failed: Vec<PrefetchFailure>,
+    bytes: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct InstallProgress {
+    completed: usize,
+    total: usize,
+}
+
+#[derive(Serialize)]
+struct RemoteAssetInfo {
+    status: u16,
+    content_length: Option<u64>,
+    content_type: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ServerStatus {
+    reachable: bool,
+    latency_ms: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+struct ClientLogLine {
+    stream: &'static str,
+    line: String,
+}
+
+#[derive(Deserialize)]
+struct LatestRelease {
+    tag_name: String,
+    html_url: String,
+}
+
+#[derive(Serialize)]
+struct UpdateInfo {
+    current: String,
+    latest: String,
+    update_available: bool,
+    download_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedClients {
+    List(Vec<SavedClient>),
+    // Settings files written before clients were ordered store them as a version -> path map
+    Legacy(HashMap<String, PathBuf>),
+}
+
+fn deserialize_clients<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<SavedClient>, D::Error> {
+    Ok(match SavedClients::deserialize(deserializer)? {
+        SavedClients::List(clients) => clients,
+        SavedClients::Legacy(clients) => {
+            let mut clients: Vec<SavedClient> = clients
+                .into_iter()
+                .map(|(version, path)| SavedClient {
+                    version,
+                    path,
+                    label: None,
+                    last_used_server: None,
+                    sha256: None,
+                    layers: Vec::new(),
+                })
+                .collect();
+            clients.sort_by(|client1, client2| client1.version.cmp(&client2.version));
+            clients
+        }
+    })
 }
 
 trait StringError<T> {
@@ -67,13 +614,108 @@ impl<T, E: Display> StringError<T> for Result<T, E> {
     }
 }
 
-type Language = HashMap<String, String>;
+// Serialized as {"code": ..., "message": ...} so the frontend can branch on the code and show its
+// own translation instead of the English message. The codes are part of the command API, so
+// existing codes must never change:
+//
+// - launch_cancelled: the launch was cancelled before the client started
+// - game_already_running: the client from a previous launch is still running
+// - missing_client: the selected client executable is not at its saved path
+// - bad_endpoint: a saved server endpoint is not a valid URL
+// - other: any other error, described only by the message
+#[derive(Debug)]
+enum AppError {
+    LaunchCancelled,
+    GameAlreadyRunning,
+    MissingClient { version: String, path: PathBuf },
+    BadEndpoint { endpoint: String, reason: String },
+    Other(String),
+}
+
+impl AppError {
+    fn code(&self) -> &'static str {
+        match self {
+            AppError::LaunchCancelled => "launch_cancelled",
+            AppError::GameAlreadyRunning => "game_already_running",
+            AppError::MissingClient { .. } => "missing_client",
+            AppError::BadEndpoint { .. } => "bad_endpoint",
+            AppError::Other(_) => "other",
+        }
+    }
+
+    fn bad_endpoint(endpoint: &str, reason: impl Display) -> Self {
+        AppError::BadEndpoint {
+            endpoint: endpoint.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::LaunchCancelled => write!(f, "The launch was cancelled"),
+            AppError::GameAlreadyRunning => write!(f, "Game is already running"),
+            AppError::MissingClient { version, path } => {
+                write!(f, "Client {} is unavailable at {}", version, path.display())
+            }
+            AppError::BadEndpoint { endpoint, reason } => {
+                write!(f, "Bad HTTPS endpoint {}: {}", endpoint, reason)
+            }
+            AppError::Other(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+#[derive(Deserialize)]
+struct Language {
+    #[serde(default)]
+    fallback: Vec<String>,
+    #[serde(flatten)]
+    values: HashMap<String, String>,
+}
+
+fn language_chain<'a>(
+    languages: &'a HashMap<String, Language>,
+    language_id: &'a str,
+) -> Vec<&'a str> {
+    let mut chain = Vec::new();
+    let mut pending = vec![DEFAULT_LANGUAGE_ID, language_id];
+
+    while let Some(next_language_id) = pending.pop() {
+        // Skip languages that were already visited to guard against fallback cycles
+        if chain.contains(&next_language_id) {
+            continue;
+        }
+
+        if let Some(language) = languages.get(next_language_id) {
+            chain.push(next_language_id);
+            pending.extend(language.fallback.iter().rev().map(String::as_str));
+        }
+    }
 
-fn language<'a>(languages: &'a HashMap<String, Language>, language_id: &String) -> &'a Language {
-    languages
-        .get(language_id)
-        .or(languages.get(DEFAULT_LANGUAGE_ID))
-        .expect("Missing default language")
+    chain
 }
 
 fn i18n_value_for_language_id_and_key(
@@ -81,7 +723,11 @@ fn i18n_value_for_language_id_and_key(
     language_id: &String,
     key: &String,
 ) -> String {
-    i18n_value_for_language_and_key(language(languages, language_id), language_id, key)
+    language_chain(languages, language_id)
+        .into_iter()
+        .find_map(|chain_language_id| languages[chain_language_id].values.get(key))
+        .unwrap_or_else(|| panic!("Requested unknown key {key} for language {language_id}"))
+        .clone()
 }
 
 fn i18n_value_for_language_and_key(
@@ -90,6 +736,7 @@ fn i18n_value_for_language_and_key(
     key: &String,
 ) -> String {
     (*language
+        .values
         .get(key)
         .unwrap_or_else(|| panic!("Requested unknown key {key} for language {language_id}")))
     .clone()
@@ -108,22 +755,274 @@ fn write_json_to_app_data<T: Serialize>(value: &T, path: &Path) -> Result<(), St
     .err_to_string("Error while writing JSON to app data")
 }
 
+fn server_header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let header_name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("Bad server header name {}", name))?;
+            let mut header_value = HeaderValue::from_str(value)
+                .map_err(|_| format!("Bad value for server header {}", name))?;
+
+            // Headers often carry API keys, so keep them out of any debug output
+            header_value.set_sensitive(true);
+            Ok((header_name, header_value))
+        })
+        .collect()
+}
+
+fn apply_saved_server(
+    proxy_config: &mut ProxyConfig,
+    saved_server: &SavedServer,
+) -> Result<(), String> {
+    proxy_config.remote_headers = server_header_map(&saved_server.headers)?;
+    proxy_config.accept_invalid_certs = saved_server.accept_invalid_certs;
+    Ok(())
+}
+
 fn save_server_list(saved_servers: &VecDeque<SavedServer>, path: &Path) -> Result<(), String> {
     write_json_to_app_data(saved_servers, path)
 }
 
+// Keeps every entry that is a valid server so that one bad entry doesn't lose the whole list
+fn parse_saved_servers_leniently(bytes: &[u8]) -> Result<(VecDeque<SavedServer>, usize), String> {
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_slice(bytes).err_to_string("Saved servers file is not a list")?;
+    let entry_count = entries.len();
+    let saved_servers: VecDeque<SavedServer> = entries
+        .into_iter()
+        .filter_map(|entry| serde_json::from_value(entry).ok())
+        .collect();
+    let dropped = entry_count - saved_servers.len();
+    Ok((saved_servers, dropped))
+}
+
+fn back_up_saved_servers(saved_servers_path: &Path, bytes: &[u8]) -> Result<PathBuf, String> {
+    let backup_path = saved_servers_path.with_extension(SAVED_SERVERS_BACKUP_EXTENSION);
+    write(&backup_path, bytes).err_to_string("Error backing up saved servers")?;
+    Ok(backup_path)
+}
+
+fn load_saved_servers_file(saved_servers_path: &Path) -> VecDeque<SavedServer> {
+    let bytes = match read(saved_servers_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            warn!("Unable to read saved servers file: {}", err);
+            return VecDeque::new();
+        }
+    };
+
+    serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+        warn!(
+            "Bad saved servers file, loading only its valid entries: {}",
+            err
+        );
+
+        // Saving a server rewrites the whole file, so keep the original before that can happen
+        match back_up_saved_servers(saved_servers_path, &bytes) {
+            Ok(backup_path) => warn!(
+                "Backed up bad saved servers file to {}",
+                backup_path.display()
+            ),
+            Err(err) => error!("{}", err),
+        }
+
+        match parse_saved_servers_leniently(&bytes) {
+            Ok((saved_servers, dropped)) => {
+                warn!("Skipped {} bad saved servers", dropped);
+                saved_servers
+            }
+            Err(err) => {
+                warn!("{}", err);
+                VecDeque::new()
+            }
+        }
+    })
+}
+
+fn sanitize_nickname(nickname: &str) -> Result<String, String> {
+    let nickname = nickname.trim();
+    if nickname.is_empty() {
+        return Err("Server nickname cannot be empty".to_string());
+    }
+
+    if nickname.chars().count() > MAX_NICKNAME_LENGTH {
+        return Err(format!(
+            "Server nickname cannot be longer than {} characters",
+            MAX_NICKNAME_LENGTH
+        ));
+    }
+
+    Ok(nickname.to_string())
+}
+
 fn detect_client_version(client_bytes: &[u8]) -> Option<String> {
     let version_regex = Regex::new(r"\d\.\d{3}\.\d\.\d{6}").expect("Unable to compile regex");
     version_regex
         .find(client_bytes)
         .and_then(|mat| String::from_utf8(Vec::from(mat.as_bytes())).ok())
+        .or_else(|| detect_pe_version(client_bytes))
+}
+
+fn detect_pe_version(client_bytes: &[u8]) -> Option<String> {
+    // Some repacked clients lack the embedded version string but keep their version resource
+    let version_info = PeFile::from_bytes(client_bytes)
+        .ok()?
+        .resources()
+        .ok()?
+        .version_info()
+        .ok()?;
+
+    // Most executables have a version resource, so only trust one that names the game
+    let names_game = version_info.translation().iter().any(|&language| {
+        ["OriginalFilename", "InternalName", "ProductName"]
+            .into_iter()
+            .filter_map(|key| version_info.value(language, key))
+            .any(|name| {
+                name.replace(char::is_whitespace, "")
+                    .to_lowercase()
+                    .contains("clonewars")
+            })
+    });
+    if !names_game {
+        return None;
+    }
+
+    // Prefer the version strings because the fixed version fields are too small for the build
+    // number. Resource editors often write the parts separated by commas.
+    let version_string = version_info
+        .translation()
+        .iter()
+        .flat_map(|&language| {
+            ["ProductVersion", "FileVersion"].map(|key| version_info.value(language, key))
+        })
+        .flatten()
+        .map(|version| version.replace(',', ".").replace(char::is_whitespace, ""))
+        .find(|version| {
+            version.split('.').count() > 1
+                && version
+                    .split('.')
+                    .all(|part| !part.is_empty() && part.chars().all(|char| char.is_ascii_digit()))
+        });
+
+    version_string.or_else(|| {
+        version_info
+            .fixed()
+            .map(|fixed_info| fixed_info.dwProductVersion.to_string())
+    })
+}
+
+fn client_sha256(client_bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(client_bytes))
+}
+
+fn is_trusted_client_hash(settings: &Settings, sha256: &str) -> Option<bool> {
+    // Without an allowlist, there's nothing to compare against
+    if settings.trusted_client_hashes.is_empty() {
+        None
+    } else {
+        Some(
+            settings
+                .trusted_client_hashes
+                .iter()
+                .any(|trusted_hash| trusted_hash.eq_ignore_ascii_case(sha256)),
+        )
+    }
+}
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
 }
 
-fn remove_missing_clients(settings: &mut Settings, settings_path: &Path) -> Result<(), String> {
+fn client_path<'a>(settings: &'a Settings, version: &str) -> Result<&'a PathBuf, String> {
     settings
         .clients
-        .retain(|_, path| path.try_exists().unwrap_or(true));
-    write_json_to_app_data(&(*settings), settings_path)
+        .iter()
+        .find(|client| client.version == version)
+        .map(|client| &client.path)
+        .ok_or_else(|| "Requested client version that does not exist".to_string())
+}
+
+fn client_directory(client_path: &Path) -> Result<PathBuf, String> {
+    client_path
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Client has no parent directory".to_string())
+}
+
+// The client's own folder comes first so that its update layers take precedence over it
+fn client_layers(settings: &Settings, version: &str) -> Result<Vec<PathBuf>, String> {
+    let client = settings
+        .clients
+        .iter()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    let mut client_folders = vec![client_directory(&client.path)?];
+    client_folders.extend(client.layers.iter().cloned());
+    Ok(client_folders)
+}
+
+fn user_agent(settings: &Settings) -> String {
+    settings
+        .user_agent
+        .clone()
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())
+}
+
+fn proxy_config(settings: &Settings, languages: &HashMap<String, Language>) -> ProxyConfig {
+    ProxyConfig {
+        max_asset_path_length: settings.proxy_max_asset_path_length,
+        max_asset_path_components: settings.proxy_max_asset_path_components,
+        redirect_policy: settings.proxy_redirect_policy,
+        user_agent: user_agent(settings),
+        listen_backlog: settings.proxy_listen_backlog,
+        tcp_keepalive: settings.proxy_tcp_keepalive,
+        debug_endpoints: settings.proxy_debug_endpoints,
+        remote_cache_dir: None,
+        asset_override_dir: settings.asset_override_dir.clone(),
+        max_remote_asset_size: settings.proxy_max_remote_asset_size,
+        unix_socket_path: settings.proxy_unix_socket_path.clone(),
+        case_insensitive_lookup: settings.proxy_case_insensitive_lookup,
+        always_remote: always_remote_regex(&settings.proxy_always_remote_patterns).unwrap_or_else(
+            |err| {
+                warn!("Ignoring bad always-remote patterns: {}", err);
+                None
+            },
+        ),
+        strip_name_hash: settings.proxy_strip_name_hash,
+        compression_cache_size: settings.proxy_compression_cache_size,
+        strict_remote_manifests: settings.proxy_strict_remote_manifests,
+        max_concurrent_pack_parses: settings.proxy_max_concurrent_pack_parses,
+        tiny_asset_thresholds: settings
+            .tiny_asset_thresholds
+            .iter()
+            .map(|(extension, threshold)| (extension.to_lowercase(), *threshold))
+            .collect(),
+        remote_headers: HeaderMap::new(),
+        accept_invalid_certs: false,
+        not_found_message: i18n_value_for_language_id_and_key(
+            languages,
+            &settings.language,
+            &PROXY_NOT_FOUND_I18N_KEY.to_string(),
+        ),
+    }
+}
+
+fn log_missing_clients(settings: &Settings) {
+    // Keep missing clients because their drive may only be disconnected temporarily
+    for client in &settings.clients {
+        if !client.path.try_exists().unwrap_or(false) {
+            warn!(
+                "Client {} is unavailable at {}",
+                client.version,
+                client.path.display()
+            );
+        }
+    }
 }
 
 fn list_files(root_dir: &Path, filter: impl Fn(&Path) -> bool) -> io::Result<Vec<PathBuf>> {
@@ -148,72 +1047,180 @@ fn list_files(root_dir: &Path, filter: impl Fn(&Path) -> bool) -> io::Result<Vec
     Ok(files)
 }
 
+fn directory_size(root_dir: &Path, max_files: usize) -> io::Result<(u64, usize)> {
+    let mut total_size = 0;
+    let mut files_scanned = 0;
+
+    let mut directories = VecDeque::new();
+    directories.push_back(root_dir.to_path_buf());
+
+    while let Some(dir) = directories.pop_front() {
+        if dir.is_dir() {
+            for entry in read_dir(dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    directories.push_back(entry.path());
+                } else {
+                    // Stop early rather than walking arbitrarily large trees
+                    if files_scanned >= max_files {
+                        return Ok((total_size, files_scanned));
+                    }
+
+                    total_size += metadata.len();
+                    files_scanned += 1;
+                }
+            }
+        }
+    }
+
+    Ok((total_size, files_scanned))
+}
+
 fn is_web_downloaded_pack(file_name: &str) -> bool {
     file_name.contains("W_") && file_name.ends_with(".pack")
 }
 
-fn should_copy(path: &Path) -> bool {
-    if let Some(file_name) = path.file_name() {
-        if let Ok(file_name_str) = file_name.to_os_string().into_string() {
-            file_name_str != ACTIVE_CLIENT_EXECUTABLE
-                && file_name_str != CLIENT_CONFIG_PATH
-                && file_name_str != USER_OPTIONS_PATH
-                && !is_web_downloaded_pack(&file_name_str)
-        } else {
-            true
-        }
+fn copy_exclusion(path: &Path) -> Option<CopyExclusionReason> {
+    let file_name_str = path.file_name()?.to_os_string().into_string().ok()?;
+    if file_name_str == ACTIVE_CLIENT_EXECUTABLE {
+        Some(CopyExclusionReason::Executable)
+    } else if file_name_str == CLIENT_CONFIG_PATH {
+        Some(CopyExclusionReason::ClientConfig)
+    } else if file_name_str == USER_OPTIONS_PATH {
+        Some(CopyExclusionReason::UserOptions)
+    } else if is_web_downloaded_pack(&file_name_str) {
+        Some(CopyExclusionReason::WebDownloadedPack)
     } else {
-        true
+        None
     }
 }
 
-fn prepare_client(
-    proxy_port: u16,
-    client_path: &Path,
-    client_parent: &Path,
-    state: &State<GlobalState>,
+fn apply_user_options_template(
+    template_path: Option<&Path>,
+    user_options_path: &Path,
 ) -> Result<(), String> {
-    create_dir_all(&state.active_client_path)
-        .err_to_string("Error while creating active client folder")?;
+    match template_path {
+        Some(template_path) if user_options_path.exists() => {
+            merge_user_options(template_path, user_options_path)
+        }
+        Some(template_path) => copy(template_path, user_options_path)
+            .map(|_| ())
+            .err_to_string("Error copying user options to active client folder"),
+        None => {
+            warn!("User options template is missing, so the client will generate its own defaults");
+            Ok(())
+        }
+    }
+}
 
-    let active_client_executable_path = state.active_client_path.join(ACTIVE_CLIENT_EXECUTABLE);
-    copy(client_path, active_client_executable_path)
-        .err_to_string("Error while copying client to active client folder")?;
+fn should_copy(path: &Path) -> bool {
+    copy_exclusion(path).is_none()
+}
 
-    let client_files_to_copy = list_files(client_parent, should_copy)
-        .err_to_string("Error while listing files in client folder")?;
-    for path in client_files_to_copy {
-        let source = client_parent.join(&path);
-        let destination = state.active_client_path.join(&path);
-        create_dir_all(
-            destination
-                .parent()
-                .expect("Active client path has no parent"),
-        )
-        .err_to_string(
-            "Error while creating parent folder for file to copy to active client folder",
-        )?;
-        copy(source, destination)
-            .err_to_string("Error while copying file to active client folder")?;
+fn merge_user_options(template_path: &Path, user_options_path: &Path) -> Result<(), String> {
+    let template =
+        Ini::load_from_file(template_path).err_to_string("Error reading user options template")?;
+    let mut user_options =
+        Ini::load_from_file(user_options_path).err_to_string("Error reading user options")?;
+
+    // Only add keys that are missing so that the user's own values are never overwritten
+    let mut changed = false;
+    for (section, properties) in template.iter() {
+        for (key, value) in properties.iter() {
+            let has_key = user_options
+                .section(section)
+                .map(|user_properties| user_properties.contains_key(key))
+                .unwrap_or(false);
+            if !has_key {
+                user_options.with_section(section).set(key, value);
+                changed = true;
+            }
+        }
     }
 
-    let user_options_path = state.active_client_path.join(USER_OPTIONS_PATH);
-    if !user_options_path.exists() {
-        copy(&state.user_options_template_path, user_options_path)
-            .err_to_string("Error copying user options to active client folder")?;
+    if changed {
+        user_options
+            .write_to_file(user_options_path)
+            .err_to_string("Error writing merged user options to active client folder")?;
     }
 
-    let proxy_url = format!("http://127.0.0.1:{}", proxy_port);
-    let proxy_assets_url = format!("{}/assets", proxy_url);
-    let proxy_card_assets_url = format!("{}/card_games/", proxy_assets_url);
-    let proxy_crash_url = format!("{}/crash?code=G", proxy_url);
-    let mut client_config = Ini::new();
-    client_config.with_section::<String>(None).set("World", "");
-    client_config
-        .with_section(Some("Paths"))
-        .set("PathScripts", "./Resources/Scripts/")
-        .set("PathUiModules", "./UI/UiModules/");
-    client_config
+    Ok(())
+}
+
+fn is_same_file(path1: &Path, path2: &Path) -> bool {
+    match (path1.canonicalize(), path2.canonicalize()) {
+        (Ok(canonical_path1), Ok(canonical_path2)) => canonical_path1 == canonical_path2,
+        _ => false,
+    }
+}
+
+fn is_inside_folder(path: &Path, folder: &Path) -> bool {
+    match (path.canonicalize(), folder.canonicalize()) {
+        (Ok(canonical_path), Ok(canonical_folder)) => canonical_path.starts_with(canonical_folder),
+        _ => false,
+    }
+}
+
+fn required_copy_space(copies: impl Iterator<Item = (PathBuf, PathBuf)>) -> u64 {
+    // Overwritten files free their old space, so only count how much each file grows
+    copies
+        .filter(|(source, destination)| !is_same_file(source, destination))
+        .map(|(source, destination)| {
+            let source_len = metadata(source).map(|data| data.len()).unwrap_or(0);
+            let destination_len = metadata(destination).map(|data| data.len()).unwrap_or(0);
+            source_len.saturating_sub(destination_len)
+        })
+        .sum()
+}
+
+fn available_space(path: &Path) -> Option<u64> {
+    let path = path.canonicalize().ok()?;
+
+    // Use the most specific disk that contains the path
+    Disks::new_with_refreshed_list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+// The check is skipped when the available space is unknown rather than blocking the launch
+fn check_copy_space(required_space: u64, available_space: Option<u64>) -> Result<(), String> {
+    match available_space {
+        Some(available_space) if required_space > available_space => Err(format!(
+            "Insufficient disk space for the active client folder: {} bytes are needed but only {} bytes are available",
+            required_space, available_space
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn proxy_url(proxy_port: u16) -> String {
+    format!("http://127.0.0.1:{}", proxy_port)
+}
+
+// Without the proxy, the client requests assets from the server itself
+fn asset_server_url(settings: &Settings, saved_server: &SavedServer) -> String {
+    if settings.direct_connect {
+        saved_server.https_endpoint.clone()
+    } else {
+        proxy_url(settings.proxy_port)
+    }
+}
+
+fn client_config(asset_server_url: &str, loading_screen_music_id: u32) -> Ini {
+    let asset_server_url = asset_server_url.trim_end_matches('/');
+    let proxy_assets_url = format!("{}/assets", asset_server_url);
+    let proxy_card_assets_url = format!("{}/card_games/", proxy_assets_url);
+    let proxy_crash_url = format!("{}/crash?code=G", asset_server_url);
+    let mut client_config = Ini::new();
+    client_config.with_section::<String>(None).set("World", "");
+    client_config
+        .with_section(Some("Paths"))
+        .set("PathScripts", "./Resources/Scripts/")
+        .set("PathUiModules", "./UI/UiModules/");
+    client_config
         .with_section(Some("Libraries"))
         .set("GraphicsDLL", "./GraphicsDriver.dll")
         .set("GraphicsDLLd", "./GraphicsDriver.dll")
@@ -225,11 +1232,79 @@ fn prepare_client(
         .set("TcgServerAddress", proxy_card_assets_url);
     client_config
         .with_section(Some("LoadingScreen"))
-        .set("LoadingScreenMusicId", "1144");
+        .set("LoadingScreenMusicId", loading_screen_music_id.to_string());
     client_config
         .with_section(Some("WebResources"))
         .set("GameCrashUrl", proxy_crash_url);
-    let client_config_path = state.active_client_path.join(CLIENT_CONFIG_PATH);
+    client_config
+}
+
+fn prepare_client(
+    asset_server_url: &str,
+    loading_screen_music_id: u32,
+    client_path: &Path,
+    client_parent: &Path,
+    state: &State<GlobalState>,
+) -> Result<(), AppError> {
+    let active_client_path = state.active_client_path();
+    create_dir_all(&active_client_path)
+        .err_to_string("Error while creating active client folder")?;
+
+    let active_client_executable_path = active_client_path.join(ACTIVE_CLIENT_EXECUTABLE);
+    let client_files_to_copy = list_files(client_parent, should_copy)
+        .err_to_string("Error while listing files in client folder")?;
+
+    // Fail before copying anything rather than leaving a half-copied client on a full disk
+    let copies = client_files_to_copy
+        .iter()
+        .map(|path| (client_parent.join(path), active_client_path.join(path)))
+        .chain([(
+            client_path.to_path_buf(),
+            active_client_executable_path.clone(),
+        )]);
+    check_copy_space(
+        required_copy_space(copies),
+        available_space(&active_client_path),
+    )?;
+
+    // Copying a file onto itself truncates it, so skip any file that's already in place
+    if !is_same_file(client_path, &active_client_executable_path) {
+        copy(client_path, active_client_executable_path)
+            .err_to_string("Error while copying client to active client folder")?;
+    }
+
+    for path in client_files_to_copy {
+        // Each file is copied whole, so stopping between files never leaves a truncated file. Any
+        // files left over from a previous client are replaced on the next launch.
+        if state.launch_cancelled.load(Ordering::SeqCst) {
+            return Err(AppError::LaunchCancelled);
+        }
+
+        let source = client_parent.join(&path);
+        let destination = active_client_path.join(&path);
+        if is_same_file(&source, &destination) {
+            continue;
+        }
+
+        create_dir_all(
+            destination
+                .parent()
+                .expect("Active client path has no parent"),
+        )
+        .err_to_string(
+            "Error while creating parent folder for file to copy to active client folder",
+        )?;
+        copy(source, destination)
+            .err_to_string("Error while copying file to active client folder")?;
+    }
+
+    apply_user_options_template(
+        state.user_options_template_path.as_deref(),
+        &active_client_path.join(USER_OPTIONS_PATH),
+    )?;
+
+    let client_config = client_config(asset_server_url, loading_screen_music_id);
+    let client_config_path = active_client_path.join(CLIENT_CONFIG_PATH);
     client_config
         .write_to_file(client_config_path)
         .err_to_string("Error writing client config to active client folder")?;
@@ -237,6 +1312,152 @@ fn prepare_client(
     Ok(())
 }
 
+// Describes how the launcher would change the config on disk to get the generated config
+fn diff_client_configs(on_disk: &Ini, generated: &Ini) -> Vec<ClientConfigChange> {
+    let mut changes = Vec::new();
+    for (section, properties) in generated.iter() {
+        for (key, new_value) in properties.iter() {
+            let old_value = on_disk
+                .section(section)
+                .and_then(|old_properties| old_properties.get(key));
+            let kind = match old_value {
+                None => ClientConfigChangeKind::Added {
+                    new_value: new_value.to_string(),
+                },
+                Some(old_value) if old_value != new_value => ClientConfigChangeKind::Changed {
+                    old_value: old_value.to_string(),
+                    new_value: new_value.to_string(),
+                },
+                Some(_) => continue,
+            };
+            changes.push(ClientConfigChange {
+                section: section.map(str::to_string),
+                key: key.to_string(),
+                kind,
+            });
+        }
+    }
+
+    for (section, properties) in on_disk.iter() {
+        for (key, old_value) in properties.iter() {
+            let is_generated = generated
+                .section(section)
+                .is_some_and(|new_properties| new_properties.contains_key(key));
+            if !is_generated {
+                changes.push(ClientConfigChange {
+                    section: section.map(str::to_string),
+                    key: key.to_string(),
+                    kind: ClientConfigChangeKind::Removed {
+                        old_value: old_value.to_string(),
+                    },
+                });
+            }
+        }
+    }
+
+    changes
+}
+
+#[tauri::command]
+fn diff_client_config(
+    index: usize,
+    version: String,
+    state: State<GlobalState>,
+) -> Result<Vec<ClientConfigChange>, String> {
+    let generated = {
+        let settings = state
+            .inner()
+            .settings
+            .lock()
+            .expect("Unable to lock settings");
+        client_path(&settings, &version)?;
+
+        let saved_servers = state
+            .inner()
+            .saved_servers
+            .lock()
+            .expect("Unable to lock saved servers");
+        let saved_server = saved_servers
+            .get(index)
+            .ok_or("Requested saved server that does not exist")?;
+        client_config(
+            &asset_server_url(&settings, saved_server),
+            settings.loading_screen_music_id,
+        )
+    };
+
+    // Nothing has been written before the first launch, so every generated key is new
+    let client_config_path = state.active_client_path().join(CLIENT_CONFIG_PATH);
+    let on_disk = if client_config_path.exists() {
+        Ini::load_from_file(&client_config_path).err_to_string("Error reading client config")?
+    } else {
+        Ini::new()
+    };
+
+    Ok(diff_client_configs(&on_disk, &generated))
+}
+
+#[tauri::command]
+fn preview_copy_plan(version: String, state: State<GlobalState>) -> Result<CopyPlan, String> {
+    let client_directory = {
+        let settings = state
+            .inner()
+            .settings
+            .lock()
+            .expect("Unable to lock settings");
+        client_directory(client_path(&settings, &version)?)?
+    };
+
+    let mut paths = list_files(&client_directory, |_| true)
+        .err_to_string("Error while listing files in client folder")?;
+    paths.sort();
+
+    let mut copy_plan = CopyPlan {
+        included: Vec::new(),
+        excluded: Vec::new(),
+    };
+    for path in paths {
+        match copy_exclusion(&path) {
+            Some(reason) => copy_plan.excluded.push(CopyExclusion { path, reason }),
+            None => copy_plan.included.push(path),
+        }
+    }
+
+    Ok(copy_plan)
+}
+
+#[tauri::command]
+fn preview_client_config(
+    index: usize,
+    version: String,
+    state: State<GlobalState>,
+) -> Result<String, String> {
+    let settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    client_path(&settings, &version)?;
+
+    let saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    let saved_server = saved_servers
+        .get(index)
+        .ok_or("Requested saved server that does not exist")?;
+
+    let mut client_config_bytes = Vec::new();
+    client_config(
+        &asset_server_url(&settings, saved_server),
+        settings.loading_screen_music_id,
+    )
+    .write_to(&mut client_config_bytes)
+    .err_to_string("Error serializing client config")?;
+    String::from_utf8(client_config_bytes).err_to_string("Client config is not valid UTF-8")
+}
+
 #[tauri::command]
 fn current_language_id(state: State<GlobalState>) -> String {
     state
@@ -272,6 +1493,29 @@ fn set_language(new_language_id: String, state: State<GlobalState>) -> Result<()
     write_json_to_app_data(&(*settings), &state.settings_path)
 }
 
+#[tauri::command]
+fn get_loading_screen_music_id(state: State<GlobalState>) -> u32 {
+    state
+        .settings
+        .lock()
+        .expect("Unable to lock settings")
+        .loading_screen_music_id
+}
+
+#[tauri::command]
+fn set_loading_screen_music_id(
+    loading_screen_music_id: u32,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    if loading_screen_music_id == 0 {
+        return Err("Loading screen music ID must not be 0".to_string());
+    }
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    settings.loading_screen_music_id = loading_screen_music_id;
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
 #[tauri::command]
 fn i18n_value_for_key(key: String, state: State<GlobalState>) -> String {
     let language_id = &state
@@ -282,135 +1526,2170 @@ fn i18n_value_for_key(key: String, state: State<GlobalState>) -> String {
     i18n_value_for_language_id_and_key(&state.languages, language_id, &key)
 }
 
+// Keys are missing even when a fallback language would fill them in, since translators still
+// need to add them
 #[tauri::command]
-fn load_saved_servers(state: State<GlobalState>) -> VecDeque<SavedServer> {
-    let saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    (*saved_servers).clone()
+fn missing_translation_keys(state: State<GlobalState>) -> HashMap<String, Vec<String>> {
+    let Some(default_language) = state.languages.get(DEFAULT_LANGUAGE_ID) else {
+        return HashMap::new();
+    };
+
+    state
+        .languages
+        .iter()
+        .filter(|(language_id, _)| language_id.as_str() != DEFAULT_LANGUAGE_ID)
+        .map(|(language_id, language)| {
+            let mut missing_keys: Vec<String> = default_language
+                .values
+                .keys()
+                .filter(|key| !language.values.contains_key(*key))
+                .cloned()
+                .collect();
+            missing_keys.sort();
+            (language_id.clone(), missing_keys)
+        })
+        .collect()
 }
 
 #[tauri::command]
-fn set_saved_server_nickname(
-    index: usize,
-    nickname: String,
-    state: State<GlobalState>,
-) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    saved_servers[index].nickname = nickname;
-    save_server_list(&saved_servers, &state.saved_servers_path)
+fn get_settings(state: State<GlobalState>) -> SettingsDto {
+    let settings = state.settings.lock().expect("Unable to lock settings");
+    SettingsDto {
+        language: settings.language.clone(),
+        proxy_port: settings.proxy_port,
+        proxy_max_asset_path_length: settings.proxy_max_asset_path_length,
+        proxy_max_asset_path_components: settings.proxy_max_asset_path_components,
+        update_url: settings.update_url.clone(),
+        proxy_redirect_policy: settings.proxy_redirect_policy,
+        user_agent: settings.user_agent.clone(),
+        proxy_listen_backlog: settings.proxy_listen_backlog,
+        proxy_tcp_keepalive: settings.proxy_tcp_keepalive,
+        proxy_debug_endpoints: settings.proxy_debug_endpoints,
+        client_env: settings.client_env.clone(),
+        asset_override_dir: settings.asset_override_dir.clone(),
+        proxy_max_remote_asset_size: settings.proxy_max_remote_asset_size,
+        trusted_client_hashes: settings.trusted_client_hashes.clone(),
+        active_client_dir: settings.active_client_dir.clone(),
+        proxy_unix_socket_path: settings.proxy_unix_socket_path.clone(),
+        proxy_case_insensitive_lookup: settings.proxy_case_insensitive_lookup,
+        on_launch_behavior: settings.on_launch_behavior,
+        proxy_always_remote_patterns: settings.proxy_always_remote_patterns.clone(),
+        proxy_strip_name_hash: settings.proxy_strip_name_hash,
+        proxy_compression_cache_size: settings.proxy_compression_cache_size,
+        generate_random_ticket: settings.generate_random_ticket,
+        random_ticket_length: settings.random_ticket_length,
+        random_ticket_charset: settings.random_ticket_charset.clone(),
+        proxy_strict_remote_manifests: settings.proxy_strict_remote_manifests,
+        direct_connect: settings.direct_connect,
+        proxy_idle_shutdown_secs: settings.proxy_idle_shutdown_secs,
+        tiny_asset_thresholds: settings.tiny_asset_thresholds.clone(),
+        proxy_max_concurrent_pack_parses: settings.proxy_max_concurrent_pack_parses,
+        loading_screen_music_id: settings.loading_screen_music_id,
+    }
 }
 
-#[tauri::command]
-fn set_saved_server_udp_endpoint(
-    index: usize,
-    udp_endpoint: String,
-    state: State<GlobalState>,
-) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    saved_servers[index].udp_endpoint = udp_endpoint;
-    save_server_list(&saved_servers, &state.saved_servers_path)
+fn validate_client_env(client_env: &HashMap<String, String>) -> Result<(), String> {
+    for (name, value) in client_env {
+        if name.is_empty() || name.contains(['=', '\0']) || value.contains('\0') {
+            return Err(format!("Bad client environment variable {}", name));
+        }
+    }
+
+    Ok(())
 }
 
-#[tauri::command]
-fn set_saved_server_https_endpoint(
-    index: usize,
-    https_endpoint: String,
-    state: State<GlobalState>,
-) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
+fn apply_live_proxy_config(state: &GlobalState, settings: &Settings) {
+    let live_proxy_config = state
+        .live_proxy_config
         .lock()
-        .expect("Unable to lock saved servers");
-    saved_servers[index].https_endpoint = https_endpoint;
-    save_server_list(&saved_servers, &state.saved_servers_path)
+        .expect("Unable to lock live proxy config");
+    if let Some(live_proxy_config) = live_proxy_config.as_ref() {
+        update_live_proxy_config(
+            &mut live_proxy_config
+                .write()
+                .expect("Unable to lock proxy config"),
+            proxy_config(settings, &state.languages),
+        );
+    }
 }
 
-#[tauri::command]
-fn add_saved_server(saved_server: SavedServer, state: State<GlobalState>) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    saved_servers.push_front(saved_server);
-    save_server_list(&saved_servers, &state.saved_servers_path)
+// Only settings that don't require rebuilding the asset map can change while the proxy runs
+fn update_live_proxy_config(config: &mut ProxyConfig, new_config: ProxyConfig) {
+    config.max_asset_path_length = new_config.max_asset_path_length;
+    config.max_asset_path_components = new_config.max_asset_path_components;
+    config.max_remote_asset_size = new_config.max_remote_asset_size;
+    config.not_found_message = new_config.not_found_message;
+    config.always_remote = new_config.always_remote;
+    config.strip_name_hash = new_config.strip_name_hash;
 }
 
 #[tauri::command]
-fn remove_saved_server(index: usize, state: State<GlobalState>) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    saved_servers.remove(index);
-    save_server_list(&saved_servers, &state.saved_servers_path)
+fn update_proxy_config(update: ProxyConfigUpdate, state: State<GlobalState>) -> Result<(), String> {
+    if update.proxy_max_remote_asset_size == Some(0) {
+        return Err("Proxy maximum remote asset size must not be 0".to_string());
+    }
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    if let Some(max_asset_path_length) = update.proxy_max_asset_path_length {
+        settings.proxy_max_asset_path_length = max_asset_path_length;
+    }
+    if let Some(max_asset_path_components) = update.proxy_max_asset_path_components {
+        settings.proxy_max_asset_path_components = max_asset_path_components;
+    }
+    if let Some(proxy_max_remote_asset_size) = update.proxy_max_remote_asset_size {
+        settings.proxy_max_remote_asset_size = proxy_max_remote_asset_size;
+    }
+
+    apply_live_proxy_config(&state, &settings);
+    write_json_to_app_data(&(*settings), &state.settings_path)
 }
 
 #[tauri::command]
-fn reorder_saved_servers(
-    old_index: usize,
-    new_index: usize,
-    state: State<GlobalState>,
-) -> Result<(), String> {
-    let mut saved_servers = state
-        .inner()
-        .saved_servers
-        .lock()
-        .expect("Unable to lock saved servers");
-    let saved_server = saved_servers
-        .remove(old_index)
-        .expect("Tried to reorder non-existent server");
-    saved_servers.insert(new_index, saved_server);
-    save_server_list(&saved_servers, &state.saved_servers_path)
-}
+fn update_settings(update: SettingsUpdate, state: State<GlobalState>) -> Result<(), String> {
+    if let Some(language) = &update.language {
+        if !state.languages.contains_key(language) {
+            return Err(format!("Unknown language {}", language));
+        }
+    }
+
+    if update.proxy_port == Some(0) {
+        return Err("Proxy port must not be 0".to_string());
+    }
+
+    if update.proxy_max_asset_path_length == Some(0)
+        || update.proxy_max_asset_path_components == Some(0)
+    {
+        return Err("Proxy asset path limits must not be 0".to_string());
+    }
+
+    if let Some(update_url) = &update.update_url {
+        Url::parse(update_url).err_to_string("Bad update URL")?;
+    }
+
+    if let Some(user_agent) = &update.user_agent {
+        HeaderValue::from_str(user_agent).err_to_string("Bad user agent")?;
+    }
+
+    if update.proxy_listen_backlog == Some(0) {
+        return Err("Proxy listen backlog must not be 0".to_string());
+    }
+
+    if let Some(client_env) = &update.client_env {
+        validate_client_env(client_env)?;
+    }
+
+    if let Some(asset_override_dir) = &update.asset_override_dir {
+        if !asset_override_dir.as_os_str().is_empty() && !asset_override_dir.is_dir() {
+            return Err(format!(
+                "Asset override folder does not exist: {}",
+                asset_override_dir.display()
+            ));
+        }
+    }
+
+    if update.proxy_max_remote_asset_size == Some(0) {
+        return Err("Proxy maximum remote asset size must not be 0".to_string());
+    }
+
+    if let Some(proxy_unix_socket_path) = &update.proxy_unix_socket_path {
+        if !proxy_unix_socket_path.as_os_str().is_empty() {
+            if cfg!(windows) {
+                return Err("Unix domain sockets are not supported on this platform".to_string());
+            }
+
+            let parent_is_dir = proxy_unix_socket_path
+                .parent()
+                .is_some_and(|parent| parent.as_os_str().is_empty() || parent.is_dir());
+            if !parent_is_dir {
+                return Err(format!(
+                    "Proxy Unix socket folder does not exist: {}",
+                    proxy_unix_socket_path.display()
+                ));
+            }
+        }
+    }
+
+    if let Some(proxy_always_remote_patterns) = &update.proxy_always_remote_patterns {
+        if proxy_always_remote_patterns
+            .iter()
+            .any(|pattern| pattern.trim().is_empty())
+        {
+            return Err("Always-remote patterns must not be empty".to_string());
+        }
+        always_remote_regex(proxy_always_remote_patterns)
+            .err_to_string("Bad always-remote patterns")?;
+    }
+
+    if update.random_ticket_length == Some(0) {
+        return Err("Random ticket length must not be 0".to_string());
+    }
+
+    if update
+        .random_ticket_charset
+        .as_ref()
+        .is_some_and(|charset| charset.is_empty())
+    {
+        return Err("Random ticket charset must not be empty".to_string());
+    }
+
+    if update.proxy_max_concurrent_pack_parses == Some(0) {
+        return Err("Proxy maximum concurrent pack parses must not be 0".to_string());
+    }
+
+    if update.loading_screen_music_id == Some(0) {
+        return Err("Loading screen music ID must not be 0".to_string());
+    }
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    if let Some(language) = update.language {
+        settings.language = language;
+    }
+    if let Some(proxy_port) = update.proxy_port {
+        settings.proxy_port = proxy_port;
+    }
+    if let Some(max_asset_path_length) = update.proxy_max_asset_path_length {
+        settings.proxy_max_asset_path_length = max_asset_path_length;
+    }
+    if let Some(max_asset_path_components) = update.proxy_max_asset_path_components {
+        settings.proxy_max_asset_path_components = max_asset_path_components;
+    }
+    if let Some(update_url) = update.update_url {
+        settings.update_url = update_url;
+    }
+    if let Some(proxy_redirect_policy) = update.proxy_redirect_policy {
+        settings.proxy_redirect_policy = proxy_redirect_policy;
+    }
+    if let Some(user_agent) = update.user_agent {
+        // An empty user agent restores the default
+        settings.user_agent = Some(user_agent).filter(|user_agent| !user_agent.trim().is_empty());
+    }
+    if let Some(proxy_listen_backlog) = update.proxy_listen_backlog {
+        settings.proxy_listen_backlog = proxy_listen_backlog;
+    }
+    if let Some(proxy_tcp_keepalive) = update.proxy_tcp_keepalive {
+        settings.proxy_tcp_keepalive = proxy_tcp_keepalive;
+    }
+    if let Some(proxy_debug_endpoints) = update.proxy_debug_endpoints {
+        settings.proxy_debug_endpoints = proxy_debug_endpoints;
+    }
+    if let Some(client_env) = update.client_env {
+        settings.client_env = client_env;
+    }
+    if let Some(asset_override_dir) = update.asset_override_dir {
+        // An empty path disables the override folder
+        settings.asset_override_dir =
+            Some(asset_override_dir).filter(|dir| !dir.as_os_str().is_empty());
+    }
+    if let Some(proxy_max_remote_asset_size) = update.proxy_max_remote_asset_size {
+        settings.proxy_max_remote_asset_size = proxy_max_remote_asset_size;
+    }
+    if let Some(trusted_client_hashes) = update.trusted_client_hashes {
+        settings.trusted_client_hashes = trusted_client_hashes;
+    }
+    if let Some(proxy_unix_socket_path) = update.proxy_unix_socket_path {
+        // An empty path disables the Unix socket listener
+        settings.proxy_unix_socket_path =
+            Some(proxy_unix_socket_path).filter(|path| !path.as_os_str().is_empty());
+    }
+    if let Some(proxy_case_insensitive_lookup) = update.proxy_case_insensitive_lookup {
+        settings.proxy_case_insensitive_lookup = proxy_case_insensitive_lookup;
+    }
+    if let Some(on_launch_behavior) = update.on_launch_behavior {
+        settings.on_launch_behavior = on_launch_behavior;
+    }
+    if let Some(proxy_always_remote_patterns) = update.proxy_always_remote_patterns {
+        settings.proxy_always_remote_patterns = proxy_always_remote_patterns;
+    }
+
+    apply_live_proxy_config(&state, &settings);
+    if let Some(proxy_strip_name_hash) = update.proxy_strip_name_hash {
+        settings.proxy_strip_name_hash = proxy_strip_name_hash;
+    }
+    if let Some(proxy_compression_cache_size) = update.proxy_compression_cache_size {
+        settings.proxy_compression_cache_size = proxy_compression_cache_size;
+    }
+    if let Some(generate_random_ticket) = update.generate_random_ticket {
+        settings.generate_random_ticket = generate_random_ticket;
+    }
+    if let Some(random_ticket_length) = update.random_ticket_length {
+        settings.random_ticket_length = random_ticket_length;
+    }
+    if let Some(random_ticket_charset) = update.random_ticket_charset {
+        settings.random_ticket_charset = random_ticket_charset;
+    }
+    if let Some(proxy_strict_remote_manifests) = update.proxy_strict_remote_manifests {
+        settings.proxy_strict_remote_manifests = proxy_strict_remote_manifests;
+    }
+    if let Some(direct_connect) = update.direct_connect {
+        settings.direct_connect = direct_connect;
+    }
+    if let Some(proxy_idle_shutdown_secs) = update.proxy_idle_shutdown_secs {
+        settings.proxy_idle_shutdown_secs = proxy_idle_shutdown_secs;
+    }
+    if let Some(tiny_asset_thresholds) = update.tiny_asset_thresholds {
+        settings.tiny_asset_thresholds = tiny_asset_thresholds;
+    }
+    if let Some(proxy_max_concurrent_pack_parses) = update.proxy_max_concurrent_pack_parses {
+        settings.proxy_max_concurrent_pack_parses = proxy_max_concurrent_pack_parses;
+    }
+    if let Some(loading_screen_music_id) = update.loading_screen_music_id {
+        settings.loading_screen_music_id = loading_screen_music_id;
+    }
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+#[tauri::command]
+fn reset_settings_to_default(keep_clients: bool, state: State<GlobalState>) -> Result<(), String> {
+    // The active client folder moves back to the default, which can't happen during a launch
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot reset settings while the game is launching")?;
+    if is_client_process_running(&proxy_process) {
+        return Err("Cannot reset settings while the game is running".to_string());
+    }
+
+    let default_settings = Settings::default();
+    move_active_client_dir(
+        &mut state
+            .active_client_path
+            .lock()
+            .expect("Unable to lock active client path"),
+        default_settings.active_client_dir.as_deref(),
+        &state.default_active_client_path,
+    )?;
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    let clients = std::mem::take(&mut settings.clients);
+    *settings = default_settings;
+    if keep_clients {
+        settings.clients = clients;
+    }
+
+    apply_live_proxy_config(&state, &settings);
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+#[tauri::command]
+async fn check_for_update(state: State<'_, GlobalState>) -> Result<UpdateInfo, String> {
+    let (update_url, user_agent) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (settings.update_url.clone(), user_agent(&settings))
+    };
+
+    let response = state
+        .http_client
+        .get(update_url)
+        .header(USER_AGENT, user_agent)
+        .timeout(UPDATE_CHECK_TIMEOUT)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .err_to_string("Error while checking for updates")?;
+    let release_bytes = response
+        .bytes()
+        .await
+        .err_to_string("Error while reading latest release")?;
+    let release: LatestRelease =
+        serde_json::from_slice(&release_bytes).err_to_string("Bad latest release")?;
+
+    let current = env!("CARGO_PKG_VERSION").to_string();
+    let latest = release.tag_name.trim_start_matches('v').to_string();
+    Ok(UpdateInfo {
+        update_available: parse_version(&latest) > parse_version(&current),
+        current,
+        latest,
+        download_url: release.html_url,
+    })
+}
+
+#[tauri::command]
+fn load_saved_servers(state: State<GlobalState>) -> VecDeque<SavedServer> {
+    let saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    (*saved_servers).clone()
+}
+
+#[tauri::command]
+fn set_saved_server_nickname(
+    index: usize,
+    nickname: String,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let nickname = sanitize_nickname(&nickname)?;
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].nickname = nickname;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_udp_endpoint(
+    index: usize,
+    udp_endpoint: String,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].udp_endpoint = udp_endpoint;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_https_endpoint(
+    index: usize,
+    https_endpoint: String,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].https_endpoint = https_endpoint;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_mirror_https_endpoints(
+    index: usize,
+    mirror_https_endpoints: Vec<String>,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].mirror_https_endpoints = mirror_https_endpoints;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_guid(
+    index: usize,
+    guid: String,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].guid = guid;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_ticket(
+    index: usize,
+    ticket: String,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers[index].ticket = ticket;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_launch_overrides(
+    index: usize,
+    launch_overrides: LaunchOverrides,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers
+        .get_mut(index)
+        .ok_or("Requested saved server that does not exist")?
+        .launch_overrides = launch_overrides;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_headers(
+    index: usize,
+    headers: HashMap<String, String>,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    server_header_map(&headers)?;
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers
+        .get_mut(index)
+        .ok_or("Requested saved server that does not exist")?
+        .headers = headers;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn set_saved_server_accept_invalid_certs(
+    index: usize,
+    accept_invalid_certs: bool,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers
+        .get_mut(index)
+        .ok_or("Requested saved server that does not exist")?
+        .accept_invalid_certs = accept_invalid_certs;
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn open_server_website(
+    index: usize,
+    app: AppHandle,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let website = {
+        let saved_servers = state
+            .inner()
+            .saved_servers
+            .lock()
+            .expect("Unable to lock saved servers");
+        let saved_server = saved_servers
+            .get(index)
+            .ok_or("Requested saved server that does not exist")?;
+        saved_server
+            .website
+            .clone()
+            .unwrap_or_else(|| saved_server.https_endpoint.clone())
+    };
+
+    // The opener passes the URL to the OS, so don't let it launch local files or other handlers
+    let url = Url::parse(&website).err_to_string("Bad server website URL")?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Server website {} is not an HTTP(S) URL", url));
+    }
+
+    shell::open(&app.shell_scope(), url.as_str(), None)
+        .err_to_string("Error while opening server website")
+}
+
+#[tauri::command]
+fn add_saved_server(
+    mut saved_server: SavedServer,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    saved_server.nickname = sanitize_nickname(&saved_server.nickname)?;
+    server_header_map(&saved_server.headers)?;
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers.push_front(saved_server);
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn repair_saved_servers(state: State<GlobalState>) -> Result<RepairReport, String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    let bytes = read(&state.saved_servers_path).err_to_string("Error reading saved servers")?;
+    let (repaired_servers, dropped) = parse_saved_servers_leniently(&bytes)?;
+
+    // Keep the original in case the dropped entries can be fixed by hand. A file that already
+    // parses may have been saved over a bad one, so don't replace the backup from startup with it.
+    let is_valid = serde_json::from_slice::<VecDeque<SavedServer>>(&bytes).is_ok();
+    let backup_path = if is_valid {
+        state
+            .saved_servers_path
+            .with_extension(SAVED_SERVERS_BACKUP_EXTENSION)
+    } else {
+        back_up_saved_servers(&state.saved_servers_path, &bytes)?
+    };
+
+    save_server_list(&repaired_servers, &state.saved_servers_path)?;
+    *saved_servers = repaired_servers;
+    info!(
+        "Repaired saved servers, keeping {} and dropping {}",
+        saved_servers.len(),
+        dropped
+    );
+    Ok(RepairReport {
+        kept: saved_servers.len(),
+        dropped,
+        backup_path,
+    })
+}
+
+#[tauri::command]
+fn remove_saved_server(index: usize, state: State<GlobalState>) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    saved_servers.remove(index);
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+#[tauri::command]
+fn reorder_saved_servers(
+    old_index: usize,
+    new_index: usize,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    let saved_server = saved_servers
+        .remove(old_index)
+        .expect("Tried to reorder non-existent server");
+    saved_servers.insert(new_index, saved_server);
+    save_server_list(&saved_servers, &state.saved_servers_path)
+}
+
+fn register_client(
+    path: PathBuf,
+    client_version: String,
+    sha256: String,
+    overwrite: bool,
+    state: &State<GlobalState>,
+) -> Result<String, String> {
+    path.parent()
+        .ok_or("Cannot select the root folder as a client")?;
+    if is_inside_folder(&path, &state.active_client_path()) {
+        return Err("Cannot add a client from inside the active client folder".to_string());
+    }
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    if is_trusted_client_hash(&settings, &sha256) == Some(false) {
+        warn!(
+            "Client {} at {} has an untrusted SHA-256 hash: {}",
+            client_version,
+            path.display(),
+            sha256
+        );
+    }
+
+    if let Some(client) = settings
+        .clients
+        .iter_mut()
+        .find(|client| client.version == client_version)
+    {
+        if !overwrite {
+            return Err(format!(
+                "A client with version {} is already added: {}",
+                client_version,
+                client.path.display()
+            ));
+        }
+
+        client.path = path;
+        client.sha256 = Some(sha256);
+    } else {
+        settings.clients.push(SavedClient {
+            version: client_version.clone(),
+            path,
+            label: None,
+            last_used_server: None,
+            sha256: Some(sha256),
+            layers: Vec::new(),
+        });
+    }
+    write_json_to_app_data(&(*settings), &state.settings_path)?;
+    Ok(client_version)
+}
+
+fn client_file_error(path: &Path, err: io::Error) -> String {
+    match err.kind() {
+        ErrorKind::NotFound => format!("The selected client does not exist: {}", path.display()),
+        ErrorKind::PermissionDenied => format!(
+            "Permission denied while reading the selected client: {}",
+            path.display()
+        ),
+        _ => format!("Error while reading selected CWA client: {}", err),
+    }
+}
+
+fn read_client_file(path: &Path) -> Result<Vec<u8>, String> {
+    let client_metadata = metadata(path).map_err(|err| client_file_error(path, err))?;
+    if !client_metadata.is_file() {
+        return Err(format!(
+            "The selected client is not a file: {}",
+            path.display()
+        ));
+    }
+
+    // Check the size first so that selecting a huge file doesn't load it all into memory
+    if client_metadata.len() == 0 {
+        return Err(format!("The selected client is empty: {}", path.display()));
+    }
+    if client_metadata.len() > MAX_CLIENT_FILE_SIZE {
+        return Err(format!(
+            "The selected client is too large ({} bytes, at most {} allowed): {}",
+            client_metadata.len(),
+            MAX_CLIENT_FILE_SIZE,
+            path.display()
+        ));
+    }
+
+    read(path).map_err(|err| client_file_error(path, err))
+}
+
+#[tauri::command]
+fn add_client(path: PathBuf, overwrite: bool, state: State<GlobalState>) -> Result<String, String> {
+    let client_bytes = read_client_file(&path)?;
+    detect_client_version(&client_bytes).map_or(
+        Err("The selected file is not an original Clone Wars Adventures client from 2014 or earlier.".to_string()),
+        |client_version| {
+            register_client(
+                path,
+                client_version,
+                client_sha256(&client_bytes),
+                overwrite,
+                &state,
+            )
+        }
+    )
+}
+
+#[tauri::command]
+fn add_client_from_folder(
+    folder: PathBuf,
+    overwrite: bool,
+    state: State<GlobalState>,
+) -> Result<String, String> {
+    let executables = list_files(&folder, |path| {
+        path.extension()
+            .map(|extension| extension.eq_ignore_ascii_case("exe"))
+            .unwrap_or(false)
+    })
+    .err_to_string("Error while listing files in selected folder")?;
+
+    let mut candidates = Vec::new();
+    for relative_path in executables {
+        let path = folder.join(relative_path);
+
+        // Other executables like installers can be far larger than a client, so skip files that
+        // can't be a client instead of reading them whole
+        let client_bytes = match read_client_file(&path) {
+            Ok(client_bytes) => client_bytes,
+            Err(err) => {
+                warn!("Skipping executable while searching for a client: {}", err);
+                continue;
+            }
+        };
+        if let Some(client_version) = detect_client_version(&client_bytes) {
+            candidates.push((path, client_version, client_sha256(&client_bytes)));
+        }
+    }
+
+    match candidates.len() {
+        0 => Err("The selected folder does not contain an original Clone Wars Adventures client from 2014 or earlier.".to_string()),
+        1 => {
+            let (path, client_version, sha256) = candidates.remove(0);
+            register_client(path, client_version, sha256, overwrite, &state)
+        }
+        _ => Err(format!(
+            "The selected folder contains multiple clients. Please select one of them: {}",
+            candidates
+                .iter()
+                .map(|(path, _, _)| path.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        )),
+    }
+}
+
+#[tauri::command]
+fn verify_client_integrity(
+    version: String,
+    state: State<GlobalState>,
+) -> Result<ClientIntegrity, String> {
+    let settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    let client = settings
+        .clients
+        .iter()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    let client_bytes = read(&client.path).err_to_string("Error while reading client")?;
+    let sha256 = client_sha256(&client_bytes);
+
+    Ok(ClientIntegrity {
+        matches_saved_hash: client
+            .sha256
+            .as_ref()
+            .map(|saved_hash| *saved_hash == sha256),
+        trusted: is_trusted_client_hash(&settings, &sha256),
+        sha256,
+    })
+}
+
+#[tauri::command]
+fn list_clients(state: State<GlobalState>) -> Vec<(String, Option<String>, PathBuf, bool)> {
+    let settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    settings
+        .clients
+        .iter()
+        .map(|client| {
+            (
+                client.version.clone(),
+                client.label.clone(),
+                client.path.clone(),
+                client.path.try_exists().unwrap_or(false),
+            )
+        })
+        .collect()
+}
+
+#[tauri::command]
+fn remove_client(version: String, state: State<GlobalState>) -> Result<(), String> {
+    let mut settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    let index = settings
+        .clients
+        .iter()
+        .position(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    settings.clients.remove(index);
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+#[tauri::command]
+fn set_client_label(
+    version: String,
+    label: Option<String>,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    let client = settings
+        .clients
+        .iter_mut()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    client.label = label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+#[tauri::command]
+fn set_client_layers(
+    version: String,
+    layers: Vec<PathBuf>,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    if let Some(layer) = layers.iter().find(|layer| !layer.is_dir()) {
+        return Err(format!("Layer {} is not a directory", layer.display()));
+    }
+
+    let mut settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    let client = settings
+        .clients
+        .iter_mut()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    client.layers = layers;
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+#[tauri::command]
+fn last_used_server(version: String, state: State<GlobalState>) -> Result<Option<usize>, String> {
+    let settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    let client = settings
+        .clients
+        .iter()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    let Some(last_used_server) = &client.last_used_server else {
+        return Ok(None);
+    };
+
+    // The remembered server may have been edited or removed since the client last started
+    let saved_servers = state
+        .inner()
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    Ok(saved_servers
+        .iter()
+        .position(|saved_server| SavedServerIdentity::from(saved_server) == *last_used_server))
+}
+
+#[tauri::command]
+fn reorder_clients(
+    old_index: usize,
+    new_index: usize,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    let mut settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+
+    // Check the indices before removing so that a bad index cannot poison the settings lock
+    let client_count = settings.clients.len();
+    if old_index >= client_count || new_index >= client_count {
+        return Err("Requested client index that does not exist".to_string());
+    }
+
+    let client = settings.clients.remove(old_index);
+    settings.clients.insert(new_index, client);
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+fn is_client_process_running(proxy_process: &Option<(JoinHandle<()>, JoinHandle<()>)>) -> bool {
+    proxy_process
+        .as_ref()
+        .map(|(_, client_process)| !client_process.is_finished())
+        .unwrap_or(false)
+}
+
+#[tauri::command]
+async fn resolve_asset(
+    version: String,
+    asset_path: String,
+    state: State<'_, GlobalState>,
+) -> Result<AssetResolution, String> {
+    let (client_folders, proxy_config) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (
+            client_layers(&settings, &version)?,
+            proxy_config(&settings, &state.languages),
+        )
+    };
+
+    resolve_client_asset(&client_folders, &asset_path, &proxy_config)
+        .await
+        .err_to_string("Error while resolving asset")
+}
+
+#[tauri::command]
+async fn find_asset_by_crc(
+    version: String,
+    crc: u32,
+    state: State<'_, GlobalState>,
+) -> Result<Vec<String>, String> {
+    let (client_folders, proxy_config) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (
+            client_layers(&settings, &version)?,
+            proxy_config(&settings, &state.languages),
+        )
+    };
+
+    find_client_assets_by_crc(&client_folders, crc, &proxy_config)
+        .await
+        .err_to_string("Error while searching assets")
+}
+
+#[tauri::command]
+async fn profile_asset_map(
+    version: String,
+    server_index: Option<usize>,
+    state: State<'_, GlobalState>,
+) -> Result<AssetMapProfile, String> {
+    let (client_folders, mut proxy_config) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (
+            client_layers(&settings, &version)?,
+            proxy_config(&settings, &state.languages),
+        )
+    };
+
+    // Remote manifests are only fetched when a server is given so that the profile can also be
+    // taken offline
+    let game_server_urls = match server_index {
+        Some(server_index) => {
+            let saved_servers = state
+                .saved_servers
+                .lock()
+                .expect("Unable to lock saved servers");
+            let saved_server = saved_servers
+                .get(server_index)
+                .ok_or("Requested saved server that does not exist")?;
+            apply_saved_server(&mut proxy_config, saved_server)?;
+            let mut game_server_urls =
+                vec![Url::parse(&saved_server.https_endpoint).err_to_string("Bad HTTPS endpoint")?];
+            for mirror_https_endpoint in &saved_server.mirror_https_endpoints {
+                game_server_urls.push(
+                    Url::parse(mirror_https_endpoint).err_to_string("Bad mirror HTTPS endpoint")?,
+                );
+            }
+            Some(game_server_urls)
+        }
+        None => None,
+    };
+
+    profile_client_asset_map(&client_folders, game_server_urls, &proxy_config)
+        .await
+        .err_to_string("Error while profiling asset map")
+}
+
+#[tauri::command]
+async fn check_packs(
+    version: String,
+    state: State<'_, GlobalState>,
+) -> Result<Vec<PackStatus>, String> {
+    let client_folders = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        client_layers(&settings, &version)?
+    };
+
+    check_client_packs(&client_folders)
+        .await
+        .err_to_string("Error while checking packs")
+}
+
+#[tauri::command]
+async fn tiny_asset_report(
+    version: String,
+    state: State<'_, GlobalState>,
+) -> Result<Vec<TinyAsset>, String> {
+    let (client_folders, proxy_config) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (
+            client_layers(&settings, &version)?,
+            proxy_config(&settings, &state.languages),
+        )
+    };
+
+    find_client_tiny_assets(&client_folders, &proxy_config)
+        .await
+        .err_to_string("Error while searching for tiny assets")
+}
+
+#[tauri::command]
+fn is_client_running(state: State<GlobalState>) -> bool {
+    // The lock is only held for a long time while a launch is in progress, so consider the
+    // client running if the lock is unavailable
+    state
+        .proxy_process
+        .try_lock()
+        .map(|proxy_process| is_client_process_running(&proxy_process))
+        .unwrap_or(true)
+}
+
+async fn running_proxy_port(state: &GlobalState) -> Option<u16> {
+    let is_proxy_running = state
+        .proxy_process
+        .lock()
+        .await
+        .as_ref()
+        .map(|(proxy_process, _)| !proxy_process.is_finished())
+        .unwrap_or(false);
+
+    // The port is left behind when the proxy is aborted, so only trust it while the proxy runs
+    if !is_proxy_running {
+        return None;
+    }
+    *state
+        .active_proxy_port
+        .lock()
+        .expect("Unable to lock active proxy port")
+}
+
+#[tauri::command]
+async fn active_proxy_port(state: State<'_, GlobalState>) -> Result<Option<u16>, String> {
+    Ok(running_proxy_port(&state).await)
+}
+
+// Server tools may need the ticket the client logged in with, which changes per launch when
+// random tickets are enabled
+#[tauri::command]
+fn last_session_ticket(state: State<GlobalState>) -> Option<String> {
+    state
+        .session_ticket
+        .lock()
+        .expect("Unable to lock session ticket")
+        .clone()
+}
+
+#[tauri::command]
+async fn test_proxy_connectivity(state: State<'_, GlobalState>) -> Result<(), String> {
+    // The settings may have changed since launch, so use the port the proxy actually bound
+    let proxy_port = running_proxy_port(&state)
+        .await
+        .ok_or("The HTTP client proxy is not running")?;
+
+    // Any HTTP response means the proxy is reachable, even if the asset itself is missing
+    state
+        .http_client
+        .get(format!(
+            "http://127.0.0.1:{}/assets/{}",
+            proxy_port, PROXY_CONNECTIVITY_ASSET
+        ))
+        .timeout(PROXY_CONNECTIVITY_TIMEOUT)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|err| {
+            format!(
+                "The HTTP client proxy is running but could not be reached on port {}: {}",
+                proxy_port, err
+            )
+        })
+}
+
+// The client relies on the generated manifest CRC, so a server used without the proxy must serve it
+async fn check_direct_asset_server(
+    https_endpoint: &Url,
+    proxy_config: &ProxyConfig,
+) -> Result<(), String> {
+    let url = https_endpoint
+        .join("assets/")
+        .and_then(|url| url.join(PROXY_CONNECTIVITY_ASSET))
+        .err_to_string("Bad HTTPS endpoint")?;
+
+    // Check with the same client the proxy would use, so the server's headers and TLS settings apply
+    let http_client = remote_asset_client(proxy_config, std::slice::from_ref(https_endpoint))
+        .err_to_string("Unable to create HTTP client")?;
+    let response = http_client
+        .get(url.clone())
+        .timeout(SERVER_PING_TIMEOUT)
+        .send()
+        .await
+        .err_to_string("Error while checking the server's asset endpoint")?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "The server cannot be used without the proxy because {} responded with status {}",
+            url,
+            response.status()
+        ));
+    }
+
+    Ok(())
+}
+
+async fn ping_server(http_client: &Client, https_endpoint: &str) -> ServerStatus {
+    let url = match Url::parse(https_endpoint)
+        .and_then(|url| url.join("assets/"))
+        .and_then(|url| url.join(PROXY_CONNECTIVITY_ASSET))
+    {
+        Ok(url) => url,
+        Err(err) => {
+            return ServerStatus {
+                reachable: false,
+                latency_ms: None,
+                error: Some(format!("Bad HTTPS endpoint {}: {}", https_endpoint, err)),
+            }
+        }
+    };
+
+    // Any HTTP response means the server is up, even if the asset itself is missing
+    let start = Instant::now();
+    match http_client
+        .get(url)
+        .timeout(SERVER_PING_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(_) => ServerStatus {
+            reachable: true,
+            latency_ms: Some(start.elapsed().as_millis() as u64),
+            error: None,
+        },
+        Err(err) => ServerStatus {
+            reachable: false,
+            latency_ms: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+fn header_str(response: &Response, name: HeaderName) -> Option<&str> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+}
+
+#[tauri::command]
+async fn remote_asset_info(
+    server_index: usize,
+    asset_path: String,
+    state: State<'_, GlobalState>,
+) -> Result<RemoteAssetInfo, String> {
+    let (https_endpoint, headers) = {
+        let saved_servers = state
+            .saved_servers
+            .lock()
+            .expect("Unable to lock saved servers");
+        let saved_server = saved_servers
+            .get(server_index)
+            .ok_or("Requested saved server that does not exist")?;
+        (
+            saved_server.https_endpoint.clone(),
+            server_header_map(&saved_server.headers)?,
+        )
+    };
+    let url = Url::parse(&https_endpoint)
+        .and_then(|url| url.join("assets/"))
+        .and_then(|url| url.join(&asset_path.replace('\\', "/")))
+        .err_to_string("Bad remote asset URL")?;
+
+    let mut response = state
+        .http_client
+        .head(url.clone())
+        .headers(headers.clone())
+        .timeout(SERVER_PING_TIMEOUT)
+        .send()
+        .await
+        .err_to_string("Error while requesting remote asset info")?;
+
+    // Some servers don't support HEAD, so ask for a single byte instead
+    let is_head_unsupported = matches!(
+        response.status(),
+        StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+    );
+    if is_head_unsupported {
+        response = state
+            .http_client
+            .get(url)
+            .headers(headers)
+            .header(RANGE, "bytes=0-0")
+            .timeout(SERVER_PING_TIMEOUT)
+            .send()
+            .await
+            .err_to_string("Error while requesting remote asset info")?;
+    }
+
+    // A partial response only describes the requested byte, so take the full length from its range
+    let content_length = if response.status() == StatusCode::PARTIAL_CONTENT {
+        header_str(&response, CONTENT_RANGE)
+            .and_then(|content_range| content_range.rsplit_once('/'))
+            .and_then(|(_, total_length)| total_length.parse().ok())
+    } else {
+        header_str(&response, CONTENT_LENGTH).and_then(|content_length| content_length.parse().ok())
+    };
+
+    Ok(RemoteAssetInfo {
+        status: response.status().as_u16(),
+        content_length,
+        content_type: header_str(&response, CONTENT_TYPE).map(str::to_string),
+    })
+}
+
+#[tauri::command]
+async fn ping_all_saved_servers(
+    state: State<'_, GlobalState>,
+) -> Result<Vec<(usize, ServerStatus)>, String> {
+    let https_endpoints: Vec<String> = state
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers")
+        .iter()
+        .map(|saved_server| saved_server.https_endpoint.clone())
+        .collect();
+
+    // Bound the number of open connections so a long server list doesn't flood the network
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_SERVER_PINGS));
+    let mut pings = JoinSet::new();
+    for (index, https_endpoint) in https_endpoints.into_iter().enumerate() {
+        let http_client = state.http_client.clone();
+        let semaphore = Arc::clone(&semaphore);
+        pings.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("Server ping semaphore closed");
+            (index, ping_server(&http_client, &https_endpoint).await)
+        });
+    }
+
+    let mut statuses = Vec::new();
+    while let Some(result) = pings.join_next().await {
+        statuses.push(result.err_to_string("Error while pinging saved server")?);
+    }
+    statuses.sort_by_key(|(index, _)| *index);
+
+    Ok(statuses)
+}
+
+#[tauri::command]
+fn clear_active_client(state: State<GlobalState>) -> Result<(), String> {
+    // Keep the lock while clearing so that a client cannot be launched at the same time
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot clear the active client while the game is launching")?;
+    if is_client_process_running(&proxy_process) {
+        return Err("Cannot clear the active client while the game is running".to_string());
+    }
+
+    let active_client_path = state.active_client_path();
+    if active_client_path.exists() {
+        remove_dir_all(&active_client_path)
+            .err_to_string("Error while removing active client folder")?;
+    }
+    create_dir_all(&active_client_path).err_to_string("Error while creating active client folder")
+}
+
+#[tauri::command]
+fn refresh_active_client_file(
+    version: String,
+    relative_path: PathBuf,
+    state: State<GlobalState>,
+) -> Result<(), String> {
+    // Only accept plain relative paths so that nothing outside either client folder is touched
+    let is_invalid_path = relative_path.as_os_str().is_empty()
+        || relative_path
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)));
+    if is_invalid_path {
+        return Err(format!("Bad client file path {}", relative_path.display()));
+    }
+    if !should_copy(&relative_path) {
+        return Err(format!(
+            "{} is not copied from the client folder",
+            relative_path.display()
+        ));
+    }
+
+    let client_directory = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        client_directory(client_path(&settings, &version)?)?
+    };
+
+    // A symbolic link could still point outside the client folder
+    let source_path = client_directory.join(&relative_path);
+    if !source_path.is_file() || !is_inside_folder(&source_path, &client_directory) {
+        return Err(format!(
+            "Client file does not exist: {}",
+            source_path.display()
+        ));
+    }
+
+    // Keep the lock while copying so that a client cannot be launched at the same time
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot refresh an active client file while the game is launching")?;
+    if is_client_process_running(&proxy_process) {
+        return Err("Cannot refresh an active client file while the game is running".to_string());
+    }
+
+    let destination_path = state.active_client_path().join(&relative_path);
+    if is_same_file(&source_path, &destination_path) {
+        return Ok(());
+    }
+    if let Some(parent) = destination_path.parent() {
+        create_dir_all(parent).err_to_string("Error while creating active client folder")?;
+    }
+    copy(&source_path, &destination_path)
+        .err_to_string("Error while copying file to active client folder")?;
+
+    Ok(())
+}
+
+fn orphaned_active_files(version: &str, state: &GlobalState) -> Result<Vec<PathBuf>, String> {
+    let client_directory = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        client_directory(client_path(&settings, version)?)?
+    };
+    let client_files: HashSet<PathBuf> = list_files(&client_directory, should_copy)
+        .err_to_string("Error while listing files in client folder")?
+        .into_iter()
+        .collect();
+
+    // Files that are never copied are created by the launcher or the game, so they aren't orphans
+    let mut orphaned_files: Vec<PathBuf> = list_files(&state.active_client_path(), should_copy)
+        .err_to_string("Error while listing files in active client folder")?
+        .into_iter()
+        .filter(|path| !client_files.contains(path))
+        .collect();
+    orphaned_files.sort();
+    Ok(orphaned_files)
+}
+
+#[tauri::command]
+fn list_orphaned_active_files(
+    version: String,
+    state: State<GlobalState>,
+) -> Result<Vec<PathBuf>, String> {
+    orphaned_active_files(&version, &state)
+}
+
+#[tauri::command]
+fn delete_orphaned_active_files(
+    version: String,
+    state: State<GlobalState>,
+) -> Result<Vec<PathBuf>, String> {
+    // Keep the lock while deleting so that a client cannot be launched at the same time
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot delete active client files while the game is launching")?;
+    if is_client_process_running(&proxy_process) {
+        return Err("Cannot delete active client files while the game is running".to_string());
+    }
+
+    let active_client_path = state.active_client_path();
+    let orphaned_files = orphaned_active_files(&version, &state)?;
+    for path in &orphaned_files {
+        remove_file(active_client_path.join(path))
+            .err_to_string("Error while deleting active client file")?;
+    }
+
+    Ok(orphaned_files)
+}
+
+fn validate_active_client_dir(dir: &Path) -> Result<(), String> {
+    // The active client folder is cleared by the launcher, so never take over a folder with files
+    if dir.exists() {
+        let mut entries = read_dir(dir).err_to_string("Unable to read active client folder")?;
+        if entries.next().is_some() {
+            return Err(format!(
+                "The active client folder must be empty: {}",
+                dir.display()
+            ));
+        }
+    }
+
+    create_dir_all(dir).err_to_string("Unable to create active client folder")?;
+    let probe_path = dir.join(".write-test");
+    write(&probe_path, []).err_to_string("Active client folder is not writable")?;
+    remove_file(&probe_path).err_to_string("Active client folder is not writable")
+}
+
+#[tauri::command]
+fn set_active_client_dir(dir: Option<PathBuf>, state: State<GlobalState>) -> Result<(), String> {
+    // Keep the lock while moving so that a client cannot be launched at the same time
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot move the active client while the game is launching")?;
+    if is_client_process_running(&proxy_process) {
+        return Err("Cannot move the active client while the game is running".to_string());
+    }
+
+    move_active_client_dir(
+        &mut state
+            .active_client_path
+            .lock()
+            .expect("Unable to lock active client path"),
+        dir.as_deref(),
+        &state.default_active_client_path,
+    )?;
+
+    let mut settings = state.settings.lock().expect("Unable to lock settings");
+    settings.active_client_dir = dir;
+    write_json_to_app_data(&(*settings), &state.settings_path)
+}
+
+// Rotated log files are named by date, so the newest one sorts last
+fn latest_log_file(log_dir: &Path) -> io::Result<Option<PathBuf>> {
+    let mut log_files = Vec::new();
+    for entry in read_dir(log_dir)? {
+        let path = entry?.path();
+        let is_log_file = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| {
+                file_name.starts_with(&format!("{}.", LOG_FILE_PREFIX))
+                    && file_name.ends_with(&format!(".{}", LOG_FILE_SUFFIX))
+            });
+        if is_log_file && path.is_file() {
+            log_files.push(path);
+        }
+    }
+
+    Ok(log_files.into_iter().max())
+}
+
+fn move_active_client_dir(
+    active_client_path: &mut PathBuf,
+    dir: Option<&Path>,
+    default_active_client_path: &Path,
+) -> Result<(), String> {
+    let new_path = dir.unwrap_or(default_active_client_path).to_path_buf();
+    if new_path == *active_client_path {
+        return Ok(());
+    }
+
+    if dir.is_some() {
+        validate_active_client_dir(&new_path)?;
+    } else {
+        create_dir_all(&new_path).err_to_string("Unable to create active client folder")?;
+    }
+
+    // The old folder only holds copied files, so remove it unless the folders overlap
+    let is_overlapping = is_inside_folder(&new_path, active_client_path)
+        || is_inside_folder(active_client_path, &new_path);
+    if !is_overlapping && active_client_path.exists() {
+        if let Err(err) = remove_dir_all(&*active_client_path) {
+            warn!("Unable to remove old active client folder: {}", err);
+        }
+    }
+
+    *active_client_path = new_path;
+    Ok(())
+}
+
+#[tauri::command]
+fn remote_cache_size(state: State<GlobalState>) -> Result<u64, String> {
+    directory_size(&state.remote_cache_path, usize::MAX)
+        .map(|(size, _)| size)
+        .err_to_string("Unable to measure remote cache folder")
+}
+
+#[tauri::command]
+fn clear_remote_cache(state: State<GlobalState>) -> Result<u64, String> {
+    // The proxy may be writing to the cache, so keep the lock while clearing
+    let proxy_process = state
+        .proxy_process
+        .try_lock()
+        .map_err(|_| "Cannot clear the remote cache while the game is launching")?;
+    let is_proxy_running = proxy_process
+        .as_ref()
+        .map(|(proxy_process, _)| !proxy_process.is_finished())
+        .unwrap_or(false);
+    if is_proxy_running {
+        return Err("Cannot clear the remote cache while the proxy is running".to_string());
+    }
+
+    let (freed_bytes, _) = directory_size(&state.remote_cache_path, usize::MAX)
+        .err_to_string("Unable to measure remote cache folder")?;
+    if state.remote_cache_path.exists() {
+        remove_dir_all(&state.remote_cache_path)
+            .err_to_string("Error while removing remote cache folder")?;
+    }
+
+    Ok(freed_bytes)
+}
+
+async fn prefetch_asset(
+    asset_path: &str,
+    http_client: &Client,
+    game_server_urls: &[Url],
+    proxy_config: &ProxyConfig,
+    remote_cache_path: &Path,
+) -> Result<u64, String> {
+    // The proxy only reads cached assets that it can verify against a CRC
+    if http_proxy::remote_cache_file(remote_cache_path, Path::new(asset_path)).is_none() {
+        return Err("Only assets requested with a CRC can be prefetched".to_string());
+    }
+
+    let (asset_name, remote_data) =
+        fetch_remote_asset(asset_path, http_client, game_server_urls, proxy_config).await?;
+
+    // Use the same key and CRC check as the proxy so that prefetched assets are served from disk
+    http_proxy::cache_remote_asset(remote_cache_path, &asset_name, &remote_data)
+        .await
+        .err_to_string("Error while writing remote cache file")?;
+    Ok(remote_data.len() as u64)
+}
+
+// Assets are fetched from the server that the client was last launched with
+fn last_used_server_source(
+    version: &str,
+    state: &GlobalState,
+) -> Result<(ProxyConfig, Vec<Url>), String> {
+    let settings = state.settings.lock().expect("Unable to lock settings");
+    let client = settings
+        .clients
+        .iter()
+        .find(|client| client.version == version)
+        .ok_or("Requested client version that does not exist")?;
+    let last_used_server = client
+        .last_used_server
+        .as_ref()
+        .ok_or("Launch this client once before downloading its assets")?;
+
+    let saved_servers = state
+        .saved_servers
+        .lock()
+        .expect("Unable to lock saved servers");
+    let saved_server = saved_servers
+        .iter()
+        .find(|saved_server| SavedServerIdentity::from(*saved_server) == *last_used_server)
+        .ok_or("The server this client last used is no longer saved")?;
+    let game_server_urls = [&saved_server.https_endpoint]
+        .into_iter()
+        .chain(&saved_server.mirror_https_endpoints)
+        .map(|https_endpoint| Url::parse(https_endpoint).err_to_string("Bad HTTPS endpoint"))
+        .collect::<Result<Vec<Url>, String>>()?;
+
+    let mut proxy_config = proxy_config(&settings, &state.languages);
+    apply_saved_server(&mut proxy_config, saved_server)?;
+    Ok((proxy_config, game_server_urls))
+}
+
+#[tauri::command]
+async fn prefetch_assets(
+    version: String,
+    asset_paths: Vec<String>,
+    state: State<'_, GlobalState>,
+) -> Result<PrefetchReport, String> {
+    let (proxy_config, game_server_urls) = last_used_server_source(&version, &state)?;
+
+    let http_client = Arc::new(
+        remote_asset_client(&proxy_config, &game_server_urls)
+            .err_to_string("Error while creating HTTP client")?,
+    );
+    let proxy_config = Arc::new(proxy_config);
+    let game_server_urls = Arc::new(game_server_urls);
+
+    // Bound the number of downloads so a long asset list doesn't flood the server
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PREFETCHES));
+    let mut prefetches = JoinSet::new();
+    for asset_path in asset_paths {
+        let http_client = Arc::clone(&http_client);
+        let proxy_config = Arc::clone(&proxy_config);
+        let game_server_urls = Arc::clone(&game_server_urls);
+        let remote_cache_path = state.remote_cache_path.clone();
+        let semaphore = Arc::clone(&semaphore);
+        prefetches.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("Prefetch semaphore closed");
+            let result = prefetch_asset(
+                &asset_path,
+                &http_client,
+                &game_server_urls,
+                &proxy_config,
+                &remote_cache_path,
+            )
+            .await;
+            (asset_path, result)
+        });
+    }
+
+    let mut report = PrefetchReport {
+        fetched: Vec::new(),
+        failed: Vec::new(),
+        bytes: 0,
+    };
+    while let Some(result) = prefetches.join_next().await {
+        let (asset_path, result) = result.err_to_string("Error while prefetching asset")?;
+        match result {
+            Ok(bytes) => {
+                report.fetched.push(asset_path);
+                report.bytes += bytes;
+            }
+            Err(error) => report.failed.push(PrefetchFailure { asset_path, error }),
+        }
+    }
+    report.fetched.sort();
+    report
+        .failed
+        .sort_by(|failure1, failure2| failure1.asset_path.cmp(&failure2.asset_path));
+
+    Ok(report)
+}
+
+async fn download_missing_asset(
+    asset_path: &str,
+    crc: u32,
+    http_client: &Client,
+    game_server_urls: &[Url],
+    proxy_config: &ProxyConfig,
+    client_directory: &Path,
+) -> Result<u64, String> {
+    let (asset_name, remote_data) =
+        fetch_remote_asset(asset_path, http_client, game_server_urls, proxy_config).await?;
+
+    // Don't complete the install with a file that the client would reject anyway
+    let downloaded_crc = crc32fast::hash(&remote_data);
+    if downloaded_crc != crc {
+        return Err(format!(
+            "Downloaded CRC {} does not match manifest CRC {}",
+            downloaded_crc, crc
+        ));
+    }
+
+    let asset_path = client_directory.join(asset_name);
+    if let Some(parent) = asset_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .err_to_string("Error while creating client folder")?;
+    }
+    tokio::fs::write(&asset_path, &remote_data)
+        .await
+        .err_to_string("Error while writing downloaded asset")?;
+    Ok(remote_data.len() as u64)
+}
+
+#[tauri::command]
+async fn complete_client_install(
+    version: String,
+    app: AppHandle,
+    state: State<'_, GlobalState>,
+) -> Result<PrefetchReport, String> {
+    // Missing assets are downloaded into the base folder rather than an update layer
+    let (client_directory, client_folders) = {
+        let settings = state.settings.lock().expect("Unable to lock settings");
+        (
+            client_directory(client_path(&settings, &version)?)?,
+            client_layers(&settings, &version)?,
+        )
+    };
+    let (proxy_config, game_server_urls) = last_used_server_source(&version, &state)?;
+
+    let missing_assets = missing_manifest_assets(&client_folders, &game_server_urls, &proxy_config)
+        .await
+        .err_to_string("Error while finding missing assets")?;
+    info!(
+        "Downloading {} assets missing from {}",
+        missing_assets.len(),
+        client_directory.display()
+    );
+
+    let http_client = Arc::new(
+        remote_asset_client(&proxy_config, &game_server_urls)
+            .err_to_string("Error while creating HTTP client")?,
+    );
+    let proxy_config = Arc::new(proxy_config);
+    let game_server_urls = Arc::new(game_server_urls);
+    let client_directory = Arc::new(client_directory);
+
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PREFETCHES));
+    let mut downloads = JoinSet::new();
+    let total = missing_assets.len();
+    for (asset_path, crc) in missing_assets {
+        let http_client = Arc::clone(&http_client);
+        let proxy_config = Arc::clone(&proxy_config);
+        let game_server_urls = Arc::clone(&game_server_urls);
+        let client_directory = Arc::clone(&client_directory);
+        let semaphore = Arc::clone(&semaphore);
+        downloads.spawn(async move {
+            let _permit = semaphore
+                .acquire_owned()
+                .await
+                .expect("Download semaphore closed");
+            let result = download_missing_asset(
+                &asset_path,
+                crc,
+                &http_client,
+                &game_server_urls,
+                &proxy_config,
+                &client_directory,
+            )
+            .await;
+            (asset_path, result)
+        });
+    }
+
+    let mut report = PrefetchReport {
+        fetched: Vec::new(),
+        failed: Vec::new(),
+        bytes: 0,
+    };
+    while let Some(result) = downloads.join_next().await {
+        let (asset_path, result) = result.err_to_string("Error while downloading asset")?;
+        match result {
+            Ok(bytes) => {
+                report.fetched.push(asset_path);
+                report.bytes += bytes;
+            }
+            Err(error) => report.failed.push(PrefetchFailure { asset_path, error }),
+        }
+
+        let completed = report.fetched.len() + report.failed.len();
+        let _ = app.emit_all(INSTALL_PROGRESS_EVENT, InstallProgress { completed, total });
+    }
+    report.fetched.sort();
+    report
+        .failed
+        .sort_by(|failure1, failure2| failure1.asset_path.cmp(&failure2.asset_path));
+
+    Ok(report)
+}
+
+#[tauri::command]
+fn tail_logs(lines: usize, state: State<GlobalState>) -> Result<Vec<String>, String> {
+    let log_path = latest_log_file(&state.log_dir)
+        .err_to_string("Unable to list log files")?
+        .ok_or("There is no log file yet")?;
+
+    // Cap the number of lines so a large request doesn't produce a huge IPC payload
+    tail_lines(&log_path, lines.min(MAX_TAIL_LOG_LINES)).err_to_string("Unable to read log file")
+}
+
+fn tail_lines(path: &Path, lines: usize) -> io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+
+    // Read backwards from the end in chunks until there are enough lines, so that a large log
+    // file is never read whole
+    let mut start = file_len;
+    let mut buffer = Vec::new();
+    while start > 0
+        && file_len - start < MAX_TAIL_LOG_BYTES
+        && buffer.iter().filter(|&&byte| byte == b'\n').count() <= lines
+    {
+        let chunk_len = TAIL_LOG_CHUNK_SIZE.min(start);
+        start -= chunk_len;
+        file.seek(SeekFrom::Start(start))?;
+        let mut chunk = vec![0; chunk_len as usize];
+        file.read_exact(&mut chunk)?;
+        chunk.append(&mut buffer);
+        buffer = chunk;
+    }
+
+    // The first line is cut off unless the file was read from its start
+    let log = String::from_utf8_lossy(&buffer);
+    let mut log_lines = log.lines();
+    if start > 0 {
+        log_lines.next();
+    }
+
+    let mut tail: Vec<String> = log_lines.rev().take(lines).map(str::to_string).collect();
+    tail.reverse();
+    Ok(tail)
+}
+
+#[tauri::command]
+async fn storage_usage(state: State<'_, GlobalState>) -> Result<StorageReport, String> {
+    let active_client_path = state.active_client_path();
+    let remote_cache_path = state.remote_cache_path.clone();
+    let log_dir = state.log_dir.clone();
+
+    spawn_blocking(move || {
+        let (active_client, active_client_files) =
+            directory_size(&active_client_path, MAX_STORAGE_SCAN_FILES)
+                .err_to_string("Unable to measure active client folder")?;
+        let (remote_cache, remote_cache_files) = directory_size(
+            &remote_cache_path,
+            MAX_STORAGE_SCAN_FILES.saturating_sub(active_client_files),
+        )
+        .err_to_string("Unable to measure remote cache folder")?;
+        let (logs, log_files) = directory_size(
+            &log_dir,
+            MAX_STORAGE_SCAN_FILES.saturating_sub(active_client_files + remote_cache_files),
+        )
+        .err_to_string("Unable to measure log folder")?;
+
+        Ok(StorageReport {
+            active_client,
+            remote_cache,
+            logs,
+            total: active_client + remote_cache + logs,
+            truncated: active_client_files + remote_cache_files + log_files
+                >= MAX_STORAGE_SCAN_FILES,
+        })
+    })
+    .await
+    .err_to_string("Unable to measure storage usage")?
+}
+
+fn client_command(
+    executable_path: &Path,
+    active_client_path: &Path,
+    client_env: &HashMap<String, String>,
+    launch_args: &LaunchArgs,
+) -> Command {
+    let mut command = Command::new(executable_path);
+    command
+        .current_dir(active_client_path)
+        .envs(client_env)
+        .args(launch_args.render())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+fn forward_client_output(
+    output: impl Read,
+    stream: &'static str,
+    client_log: Arc<Mutex<Option<File>>>,
+    app: AppHandle,
+) {
+    let mut reader = BufReader::new(output);
+    let mut line_bytes = Vec::new();
+    loop {
+        // Split very long lines so that a chatty client can't grow the buffer without bound
+        line_bytes.clear();
+        match reader
+            .by_ref()
+            .take(MAX_CLIENT_LOG_LINE_LENGTH)
+            .read_until(b'\n', &mut line_bytes)
+        {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(err) => {
+                warn!("Unable to read client {}: {}", stream, err);
+                break;
+            }
+        }
+
+        let line = String::from_utf8_lossy(&line_bytes).trim_end().to_string();
+        if let Some(client_log) = client_log
+            .lock()
+            .expect("Unable to lock client log")
+            .as_mut()
+        {
+            if let Err(err) = writeln!(client_log, "[{}] {}", stream, line) {
+                warn!("Unable to write to client log: {}", err);
+            }
+        }
+
+        let _ = app.emit_all(CLIENT_LOG_EVENT, ClientLogLine { stream, line });
+    }
+}
+
+#[tauri::command]
+async fn client_resource_usage(state: State<'_, GlobalState>) -> Result<ResourceUsage, String> {
+    let pid = state
+        .client_pid
+        .lock()
+        .expect("Unable to lock client PID")
+        .ok_or("The client is not running")?;
+
+    spawn_blocking(move || {
+        let pid = Pid::from_u32(pid);
+        let mut system = System::new();
+
+        // CPU usage is measured between two refreshes, and the client may exit in between
+        let mut is_running = system.refresh_process(pid);
+        if is_running {
+            thread::sleep(MINIMUM_CPU_UPDATE_INTERVAL);
+            is_running = system.refresh_process(pid);
+        }
+
+        system
+            .process(pid)
+            .filter(|_| is_running)
+            .map(|process| ResourceUsage {
+                cpu_percent: process.cpu_usage(),
+                memory_bytes: process.memory(),
+            })
+            .ok_or_else(|| "The client is not running".to_string())
+    })
+    .await
+    .err_to_string("Unable to measure client resource usage")?
+}
+
+#[tauri::command]
+fn last_session_remote_assets(state: State<GlobalState>) -> Vec<String> {
+    state
+        .remote_assets
+        .lock()
+        .expect("Unable to lock remote assets")
+        .iter()
+        .cloned()
+        .collect()
+}
+
+#[tauri::command]
+fn cancel_launch(state: State<GlobalState>) {
+    // The flag stops file copying, while the abort handle stops the asset map build
+    state.launch_cancelled.store(true, Ordering::SeqCst);
+    if let Some(abort_handle) = state
+        .launch_abort_handle
+        .lock()
+        .expect("Unable to lock launch abort handle")
+        .as_ref()
+    {
+        abort_handle.abort();
+    }
+}
+
+fn apply_on_launch_behavior(app: &AppHandle, on_launch_behavior: OnLaunchBehavior) {
+    let Some(window) = app.get_window(MAIN_WINDOW_LABEL) else {
+        warn!("Unable to find the launcher window");
+        return;
+    };
+
+    // The proxy runs inside the launcher, so exiting has to wait until the client is done
+    let result = match on_launch_behavior {
+        OnLaunchBehavior::Stay => Ok(()),
+        OnLaunchBehavior::Minimize => window.minimize(),
+        OnLaunchBehavior::Tray | OnLaunchBehavior::Exit => window.hide(),
+    };
+    if let Err(err) = result {
+        warn!("Unable to apply launch behavior: {}", err);
+    }
+}
+
+async fn supervise_proxy<P, F, R>(
+    proxy_future: P,
+    run_prepare_proxy: F,
+    client_pid: Arc<Mutex<Option<u32>>>,
+    active_proxy_port: Arc<Mutex<Option<u16>>>,
+    launch_in_progress: Arc<AtomicBool>,
+    last_alive: Arc<Mutex<Option<tokio::time::Instant>>>,
+    idle_shutdown: Option<Duration>,
+) where
+    P: Future<Output = ()> + Send + 'static,
+    F: Fn() -> R,
+    R: Future<Output = io::Result<(P, oneshot::Receiver<u16>)>>,
+{
+    // Dropping the set aborts the running proxy, so stopping the supervisor stops the proxy too
+    let mut proxy = JoinSet::new();
+    proxy.spawn(proxy_future);
+
+    let mut restarts = 0;
+    let mut idle_start = None;
+    loop {
+        let Ok(result) = timeout(PROXY_IDLE_CHECK_INTERVAL, proxy.join_next()).await else {
+            // A relaunch may reuse the proxy, so it only counts as idle between sessions
+            let is_in_use = client_pid
+                .lock()
+                .expect("Unable to lock client PID")
+                .is_some()
+                || launch_in_progress.load(Ordering::SeqCst);
+            if is_in_use {
+                idle_start = None;
+                continue;
+            }
+
+            // Process detection can miss a running client, so a recent /alive request also counts
+            let idle_start = *idle_start.get_or_insert_with(Instant::now);
+            let idle_start = last_alive
+                .lock()
+                .expect("Unable to lock last alive time")
+                .map_or(idle_start, |last_alive| {
+                    idle_start.max(last_alive.into_std())
+                });
+            if idle_shutdown.is_some_and(|idle_shutdown| idle_start.elapsed() >= idle_shutdown) {
+                info!("HTTP client proxy stopping because the idle shutdown timeout elapsed");
+                return;
+            }
+            continue;
+        };
+
+        match result {
+            Some(Err(err)) if err.is_panic() => error!("HTTP client proxy crashed: {}", err),
+            Some(Err(err)) => error!("HTTP client proxy stopped: {}", err),
+            Some(Ok(())) => warn!("HTTP client proxy stopped"),
+            None => {}
+        }
+
+        // Once the client exits, nothing needs the proxy anymore
+        let is_client_running = client_pid
+            .lock()
+            .expect("Unable to lock client PID")
+            .is_some();
+        if !is_client_running {
+            return;
+        }
 
-#[tauri::command]
-fn add_client(path: PathBuf, state: State<GlobalState>) -> Result<String, String> {
-    let client_bytes =
-        read(path.clone()).err_to_string("Error while reading selected CWA client")?;
-    detect_client_version(&client_bytes).map_or(
-        Err("The selected file is not an original Clone Wars Adventures client from 2014 or earlier.".to_string()),
-        |client_version| {
-            path.parent().ok_or("Cannot select the root folder as a client")?;
+        if restarts >= MAX_PROXY_RESTARTS {
+            error!(
+                "HTTP client proxy will not be restarted again after {} attempts",
+                restarts
+            );
+            return;
+        }
+        restarts += 1;
+        warn!(
+            "Restarting HTTP client proxy while the client is running (attempt {} of {})",
+            restarts, MAX_PROXY_RESTARTS
+        );
 
-            let mut settings = state.settings.lock().expect("Unable to lock settings");
-            settings.clients.insert(client_version.clone(), path);
-            write_json_to_app_data(&(*settings), &state.settings_path)?;
-            Ok(client_version)
+        sleep(PROXY_RESTART_DELAY).await;
+        match run_prepare_proxy().await {
+            Ok((proxy_future, proxy_ready)) => {
+                proxy.spawn(proxy_future);
+                if let Ok(Ok(port)) = timeout(PROXY_READY_TIMEOUT, proxy_ready).await {
+                    *active_proxy_port
+                        .lock()
+                        .expect("Unable to lock active proxy port") = Some(port);
+                }
+            }
+            Err(err) => error!("Unable to restart HTTP client proxy: {}", err),
         }
-    )
+    }
 }
 
-#[tauri::command]
-fn list_clients(state: State<GlobalState>) -> Vec<(String, PathBuf)> {
-    let settings = state
-        .inner()
-        .settings
-        .lock()
-        .expect("Unable to lock settings");
-    settings.clients.clone().into_iter().collect()
+fn random_ticket(length: usize, charset: &str) -> Result<String, String> {
+    // The settings file can be edited by hand, so don't rely on update_settings' validation
+    let charset: Vec<char> = charset.chars().collect();
+    if charset.is_empty() {
+        return Err("Random ticket charset must not be empty".to_string());
+    }
+
+    let mut rng = thread_rng();
+    Ok((0..length)
+        .filter_map(|_| charset.choose(&mut rng))
+        .collect())
+}
+
+// Some servers reject tickets that were already used, so optionally generate a new one per launch
+fn session_ticket(settings: &Settings, saved_server: &SavedServer) -> Result<String, String> {
+    if settings.generate_random_ticket {
+        random_ticket(
+            settings.random_ticket_length,
+            &settings.random_ticket_charset,
+        )
+    } else {
+        Ok(saved_server.ticket.clone())
+    }
 }
 
 #[tauri::command]
 async fn start_client(
     index: usize,
     version: String,
+    app: AppHandle,
     state: State<'_, GlobalState>,
-) -> Result<(), String> {
-    let (proxy_port, client_directory, udp_endpoint, https_endpoint) = {
+) -> Result<(), AppError> {
+    // A double click could otherwise start two launches that race to bind the proxy port
+    let _launch_guard = LaunchGuard::acquire(&state.launch_in_progress)?;
+
+    state.launch_cancelled.store(false, Ordering::SeqCst);
+
+    let (
+        proxy_port,
+        proxy_config,
+        client_folders,
+        server_identity,
+        https_endpoint,
+        mirror_https_endpoints,
+        client_env,
+        launch_args,
+        on_launch_behavior,
+        direct_connect,
+        idle_shutdown,
+    ) = {
         let settings = state
             .inner()
             .settings
@@ -418,15 +3697,17 @@ async fn start_client(
             .expect("Unable to lock settings");
 
         let proxy_port = settings.proxy_port;
-        let client_path = settings
-            .clients
-            .get(&version)
-            .ok_or("Requested client version that does not exist")?;
-        let client_directory = client_path
-            .parent()
-            .ok_or("Client has no parent directory")?
-            .to_path_buf();
-        prepare_client(proxy_port, client_path, &client_directory, &state)?;
+        let mut proxy_config = proxy_config(&settings, &state.languages);
+        proxy_config.remote_cache_dir = Some(state.remote_cache_path.clone());
+        let client_path = client_path(&settings, &version)?;
+        if !client_path.try_exists().unwrap_or(false) {
+            return Err(AppError::MissingClient {
+                version,
+                path: client_path.clone(),
+            });
+        }
+        let client_directory = client_directory(client_path)?;
+        let client_folders = client_layers(&settings, &version)?;
 
         let saved_servers = state
             .inner()
@@ -434,115 +3715,421 @@ async fn start_client(
             .lock()
             .expect("Unable to lock saved servers");
 
-        let udp_endpoint = saved_servers[index].udp_endpoint.clone();
-        let https_endpoint =
-            Url::parse(&saved_servers[index].https_endpoint).err_to_string("Bad HTTPS endpoint")?;
+        let saved_server = saved_servers
+            .get(index)
+            .ok_or("Requested saved server that does not exist")?;
+        let server_identity = SavedServerIdentity::from(saved_server);
+        apply_saved_server(&mut proxy_config, saved_server)?;
+        let https_endpoint = Url::parse(&saved_server.https_endpoint)
+            .map_err(|err| AppError::bad_endpoint(&saved_server.https_endpoint, err))?;
+        let mirror_https_endpoints = saved_server
+            .mirror_https_endpoints
+            .iter()
+            .map(|mirror_https_endpoint| {
+                Url::parse(mirror_https_endpoint)
+                    .map_err(|err| AppError::bad_endpoint(mirror_https_endpoint, err))
+            })
+            .collect::<Result<Vec<Url>, AppError>>()?;
+        let launch_args = LaunchArgs::new(saved_server, session_ticket(&settings, saved_server)?);
+        let asset_server_url = asset_server_url(&settings, saved_server);
+        drop(saved_servers);
+
+        prepare_client(
+            &asset_server_url,
+            settings.loading_screen_music_id,
+            client_path,
+            &client_directory,
+            &state,
+        )?;
 
-        (proxy_port, client_directory, udp_endpoint, https_endpoint)
+        (
+            proxy_port,
+            proxy_config,
+            client_folders,
+            server_identity,
+            https_endpoint,
+            mirror_https_endpoints,
+            settings.client_env.clone(),
+            launch_args,
+            settings.on_launch_behavior,
+            settings.direct_connect,
+            (settings.proxy_idle_shutdown_secs > 0)
+                .then(|| Duration::from_secs(settings.proxy_idle_shutdown_secs)),
+        )
     };
 
+    *state
+        .session_ticket
+        .lock()
+        .expect("Unable to lock session ticket") = Some(launch_args.ticket.clone());
+
     let mut proxy_process_lock = state.proxy_process.lock().await;
-    if let Some((old_proxy_process, ref mut old_client_process)) = &mut *proxy_process_lock {
+    if let Some((_, old_client_process)) = &*proxy_process_lock {
         if !old_client_process.is_finished() {
-            return Err("Game is already running".to_string());
+            return Err(AppError::GameAlreadyRunning);
         }
+    }
 
-        println!("Previous proxy stopping");
-        old_proxy_process.abort();
+    // Relaunching before the idle shutdown skips rebuilding the asset map, so only the live proxy
+    // settings apply to a reused proxy
+    let proxy_session = ProxySession {
+        client_folders: client_folders.clone(),
+        server_identity: server_identity.clone(),
+        proxy_port,
+    };
+    let mut reusable_proxy_process = None;
+    if let Some((old_proxy_process, _)) = proxy_process_lock.take() {
+        let is_same_session = state
+            .proxy_session
+            .lock()
+            .expect("Unable to lock proxy session")
+            .as_ref()
+            == Some(&proxy_session);
+        if is_same_session && !direct_connect && !old_proxy_process.is_finished() {
+            info!("Reusing proxy from the previous session");
+            reusable_proxy_process = Some(old_proxy_process);
+        } else {
+            info!("Previous proxy stopping");
+            old_proxy_process.abort();
+        }
     }
 
-    let proxy_future = prepare_proxy(proxy_port, &client_directory, https_endpoint)
-        .await
-        .err_to_string("Error while starting HTTP client proxy")?;
+    let proxy_process = if direct_connect {
+        check_direct_asset_server(&https_endpoint, &proxy_config).await?;
+        info!(
+            "Connecting directly to {} without the proxy",
+            https_endpoint
+        );
+
+        // There is no proxy to supervise, so store a finished task in its place
+        *state
+            .active_proxy_port
+            .lock()
+            .expect("Unable to lock active proxy port") = None;
+        spawn(async {})
+    } else if let Some(proxy_process) = reusable_proxy_process {
+        proxy_process
+    } else {
+        // Each proxy session starts with an empty record of remote assets
+        state
+            .remote_assets
+            .lock()
+            .expect("Unable to lock remote assets")
+            .clear();
+        let remote_assets = Arc::clone(&state.remote_assets);
+        let last_alive = Arc::new(Mutex::new(None));
+        let proxy_last_alive = Arc::clone(&last_alive);
+
+        // Share the config with the running proxy so that per-request settings apply right away
+        let proxy_config = Arc::new(RwLock::new(proxy_config));
+        *state
+            .live_proxy_config
+            .lock()
+            .expect("Unable to lock live proxy config") = Some(Arc::clone(&proxy_config));
+        let run_prepare_proxy = move || {
+            let client_folders = client_folders.clone();
+            let https_endpoint = https_endpoint.clone();
+            let mirror_https_endpoints = mirror_https_endpoints.clone();
+            let proxy_config = Arc::clone(&proxy_config);
+            let remote_assets = Arc::clone(&remote_assets);
+            let last_alive = Arc::clone(&proxy_last_alive);
+            async move {
+                prepare_proxy(
+                    proxy_port,
+                    &client_folders,
+                    https_endpoint,
+                    mirror_https_endpoints,
+                    proxy_config,
+                    remote_assets,
+                    last_alive,
+                )
+                .await
+            }
+        };
+
+        // Build the asset map in its own task so that cancel_launch can abort it
+        let prepare_proxy_task = spawn(run_prepare_proxy());
+        *state
+            .launch_abort_handle
+            .lock()
+            .expect("Unable to lock launch abort handle") = Some(prepare_proxy_task.abort_handle());
+        if state.launch_cancelled.load(Ordering::SeqCst) {
+            prepare_proxy_task.abort();
+        }
 
-    let proxy_process = spawn(proxy_future);
+        let prepare_proxy_result = prepare_proxy_task.await;
+        *state
+            .launch_abort_handle
+            .lock()
+            .expect("Unable to lock launch abort handle") = None;
+        let (proxy_future, proxy_ready) = match prepare_proxy_result {
+            Ok(result) => result.err_to_string("Error while starting HTTP client proxy")?,
+            Err(err) if err.is_cancelled() => return Err(AppError::LaunchCancelled),
+            Err(err) => {
+                return Err(format!("Error while starting HTTP client proxy: {}", err).into())
+            }
+        };
+
+        let proxy_process = spawn(supervise_proxy(
+            proxy_future,
+            run_prepare_proxy,
+            Arc::clone(&state.client_pid),
+            Arc::clone(&state.active_proxy_port),
+            Arc::clone(&state.launch_in_progress),
+            last_alive,
+            idle_shutdown,
+        ));
+
+        // Don't start the client until the proxy can serve its first asset requests
+        let Ok(Ok(port)) = timeout(PROXY_READY_TIMEOUT, proxy_ready).await else {
+            proxy_process.abort();
+            return Err("The HTTP client proxy did not become ready in time".into());
+        };
+        *state
+            .active_proxy_port
+            .lock()
+            .expect("Unable to lock active proxy port") = Some(port);
+
+        proxy_process
+    };
+    *state
+        .proxy_session
+        .lock()
+        .expect("Unable to lock proxy session") = (!direct_connect).then_some(proxy_session);
 
-    let active_client_path = state.active_client_path.clone();
+    let active_client_path = state.active_client_path();
     let active_client_executable_path = active_client_path.join(ACTIVE_CLIENT_EXECUTABLE);
+
+    // Antivirus software sometimes quarantines the copied executable before it can start, which
+    // would otherwise make the launch silently do nothing
+    let is_executable_present = metadata(&active_client_executable_path)
+        .map(|executable_metadata| executable_metadata.len() > 0)
+        .unwrap_or(false);
+    if !is_executable_present {
+        proxy_process.abort();
+        return Err(format!(
+            "The active client executable at {} is missing or empty. Antivirus software may have quarantined it, so try adding an exclusion for {}",
+            active_client_executable_path.display(),
+            active_client_path.display()
+        )
+        .into());
+    }
+
+    let client_log_path = state.log_dir.join(CLIENT_LOG_FILE_NAME);
+    let client_pid = Arc::clone(&state.client_pid);
     let client_process = spawn_blocking(move || {
-        let command = Command::new(active_client_executable_path)
-            .current_dir(active_client_path)
-            .arg(format!("inifile={}", CLIENT_CONFIG_PATH))
-            .arg("Guid=1")
-            .arg(format!("Server={}", udp_endpoint))
-            .arg("Ticket=p7w9dGPBPbbm9ZG")
-            .arg("Internationalization:Locale=8")
-            .arg("LoadingScreenId=-1")
-            .arg("LiveGamer=1")
-            .spawn();
+        let command = client_command(
+            &active_client_executable_path,
+            &active_client_path,
+            &client_env,
+            &launch_args,
+        )
+        .spawn();
         match command {
-            Ok(process) => {
-                let possible_output = process.wait_with_output();
-                match possible_output {
-                    Ok(output) => {
-                        println!(
-                            "Client finished with status code: {}\nstdout:\n{}\nstderr:\n{}",
-                            output.status,
-                            String::from_utf8_lossy(&output.stdout),
-                            String::from_utf8_lossy(&output.stderr)
-                        );
+            Ok(mut process) => {
+                *client_pid.lock().expect("Unable to lock client PID") = Some(process.id());
+                apply_on_launch_behavior(&app, on_launch_behavior);
+
+                // Keep the game's own output separate from the launcher and proxy logs
+                let client_log = match File::create(&client_log_path) {
+                    Ok(file) => Some(file),
+                    Err(err) => {
+                        warn!("Unable to create client log file: {}", err);
+                        None
                     }
-                    Err(err) => println!("Failed to wait for client to finish: {}", err),
+                };
+                let client_log = Arc::new(Mutex::new(client_log));
+
+                let mut output_threads = Vec::new();
+                if let Some(stdout) = process.stdout.take() {
+                    let client_log = Arc::clone(&client_log);
+                    let app = app.clone();
+                    output_threads.push(thread::spawn(move || {
+                        forward_client_output(stdout, "stdout", client_log, app)
+                    }));
+                }
+                if let Some(stderr) = process.stderr.take() {
+                    let client_log = Arc::clone(&client_log);
+                    let app = app.clone();
+                    output_threads.push(thread::spawn(move || {
+                        forward_client_output(stderr, "stderr", client_log, app)
+                    }));
+                }
+
+                let possible_status = process.wait();
+                *client_pid.lock().expect("Unable to lock client PID") = None;
+                for output_thread in output_threads {
+                    let _ = output_thread.join();
+                }
+
+                match possible_status {
+                    Ok(status) => info!("Client finished with status code: {}", status),
+                    Err(err) => error!("Failed to wait for client to finish: {}", err),
+                }
+
+                if matches!(on_launch_behavior, OnLaunchBehavior::Exit) {
+                    app.exit(0);
                 }
             }
-            Err(err) => println!("Client failed to start: {}", err),
+            Err(err) => error!("Client failed to start: {}", err),
         }
     });
 
     *proxy_process_lock = Some((proxy_process, client_process));
 
+    let mut settings = state
+        .inner()
+        .settings
+        .lock()
+        .expect("Unable to lock settings");
+    if let Some(client) = settings
+        .clients
+        .iter_mut()
+        .find(|client| client.version == version)
+    {
+        client.last_used_server = Some(server_identity);
+    }
+    write_json_to_app_data(&(*settings), &state.settings_path)?;
     Ok(())
 }
 
+struct LogEventWriter(Sender<String>);
+
+impl io::Write for LogEventWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Nobody is listening if the forwarding thread stopped, so there's nothing to report
+        let _ = self.0.send(String::from_utf8_lossy(buf).into_owned());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn load_languages(languages_path: Option<PathBuf>) -> HashMap<String, Language> {
+    let languages = languages_path
+        .ok_or_else(|| "Unable to resolve languages file".to_string())
+        .and_then(|path| read(path).err_to_string("Missing languages file"))
+        .and_then(|bytes| serde_json::from_slice(&bytes).err_to_string("Bad languages file"));
+
+    // Fall back to the languages compiled into the launcher so that it can still start
+    languages.unwrap_or_else(|err| {
+        error!("Using built-in languages: {}", err);
+        serde_json::from_str(EMBEDDED_I18N_GLOBAL_CONFIG).expect("Bad built-in languages file")
+    })
+}
+
+fn init_logging(log_dir: &Path) -> Receiver<String> {
+    // Per-request spans are logged at the debug level, so only the console in debug builds shows
+    // them rather than every asset request filling the log file and the launcher's log view
+    let targets = |level| {
+        Targets::new()
+            .with_target(env!("CARGO_CRATE_NAME"), level)
+            .with_default(Level::WARN)
+    };
+
+    // Always log to a file so that release builds without a console can still be diagnosed
+    let log_file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)
+        .expect("Unable to create log file");
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(log_file)
+        .with_filter(targets(Level::INFO));
+    let stdout_layer = cfg!(debug_assertions).then(|| {
+        tracing_subscriber::fmt::layer()
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(targets(Level::DEBUG))
+    });
+
+    let (log_sender, log_receiver) = channel();
+    let event_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(move || LogEventWriter(log_sender.clone()))
+        .with_filter(targets(Level::INFO));
+
+    tracing_subscriber::registry()
+        .with(file_layer)
+        .with(stdout_layer)
+        .with(event_layer)
+        .init();
+
+    log_receiver
+}
+
 fn main() {
     tauri::Builder::default()
+        .system_tray(SystemTray::new())
+        .on_system_tray_event(|app, event| {
+            // Bring back a launcher that was hidden when the client started
+            if let SystemTrayEvent::LeftClick { .. } = event {
+                if let Some(window) = app.get_window(MAIN_WINDOW_LABEL) {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+        })
         .setup(|app| {
+            let log_dir = app
+                .path_resolver()
+                .app_log_dir()
+                .expect("Unable to resolve app log directory");
+            let log_receiver = init_logging(&log_dir);
+            let app_handle = app.handle();
+            thread::spawn(move || {
+                for log_line in log_receiver {
+                    let _ = app_handle.emit_all(LOG_LINE_EVENT, log_line.trim_end());
+                }
+            });
+
             let app_data_dir = app
                 .path_resolver()
                 .app_data_dir()
                 .expect("Unable to resolve app data directory");
 
             let saved_servers_path = app_data_dir.join(SAVED_SERVERS_PATH);
-            let saved_servers: VecDeque<SavedServer> = match read(&saved_servers_path) {
-                Ok(bytes) => serde_json::from_slice(&bytes).expect("Bad saved servers config file"),
-                Err(err) => {
-                    println!("Unable to read saved servers file: {}", err);
-                    VecDeque::new()
-                }
-            };
+            let saved_servers = load_saved_servers_file(&saved_servers_path);
 
             let settings_path = app_data_dir.join(USER_SETTINGS_PATH);
-            let mut settings: Settings = match read(&settings_path) {
-                Ok(bytes) => serde_json::from_slice(&bytes).expect("Bad saved servers config file"),
+            let settings: Settings = match read(&settings_path) {
+                Ok(bytes) => serde_json::from_slice(&bytes).expect("Bad settings file"),
                 Err(err) => {
-                    println!("Unable to read settings file: {}", err);
-                    Settings {
-                        clients: HashMap::new(),
-                        language: DEFAULT_LANGUAGE_ID.to_string(),
-                        proxy_port: 4001,
-                    }
+                    warn!("Unable to read settings file: {}", err);
+                    Settings::default()
                 }
             };
-            if let Err(err) = remove_missing_clients(&mut settings, &settings_path) {
-                println!(
-                    "Unable to save settings file after removing missing clients: {}",
-                    err
-                );
-            }
+            log_missing_clients(&settings);
 
-            let languages_path = app
-                .path_resolver()
-                .resolve_resource(I18N_GLOBAL_CONFIG_PATH)
-                .expect("Unable to resolve languages file");
-            let languages: HashMap<String, Language> =
-                serde_json::from_slice(&read(languages_path).expect("Missing languages file"))
-                    .expect("Bad languages file");
+            let languages = load_languages(
+                app.path_resolver()
+                    .resolve_resource(I18N_GLOBAL_CONFIG_PATH),
+            );
 
-            let active_client_path = app_data_dir.join("active_client/");
+            let default_active_client_path = app_data_dir.join("active_client/");
+            let active_client_path = settings
+                .active_client_dir
+                .clone()
+                .unwrap_or_else(|| default_active_client_path.clone());
+            let remote_cache_path = app_data_dir.join("remote_cache/");
             let user_options_template_path = app
                 .path_resolver()
                 .resolve_resource(USER_OPTIONS_TEMPLATE_PATH)
-                .expect("Unable to resolve user options template file");
+                .filter(|path| path.exists());
+            if user_options_template_path.is_none() {
+                warn!("Unable to find user options template file");
+            }
+
+            let http_client = Client::builder()
+                .user_agent(DEFAULT_USER_AGENT)
+                .build()
+                .expect("Unable to build HTTP client");
 
             app.manage(GlobalState {
                 settings_path,
@@ -550,9 +4137,22 @@ fn main() {
                 saved_servers: Mutex::new(saved_servers),
                 languages,
                 settings: Mutex::new(settings),
-                active_client_path,
+                active_client_path: Mutex::new(active_client_path),
+                default_active_client_path,
+                remote_cache_path,
+                log_dir,
                 user_options_template_path,
                 proxy_process: tokio::sync::Mutex::new(None),
+                http_client,
+                client_pid: Arc::new(Mutex::new(None)),
+                remote_assets: Arc::new(Mutex::new(BTreeSet::new())),
+                launch_cancelled: AtomicBool::new(false),
+                launch_in_progress: Arc::new(AtomicBool::new(false)),
+                launch_abort_handle: Mutex::new(None),
+                live_proxy_config: Mutex::new(None),
+                session_ticket: Mutex::new(None),
+                active_proxy_port: Arc::new(Mutex::new(None)),
+                proxy_session: Mutex::new(None),
             });
 
             Ok(())
@@ -561,18 +4161,840 @@ fn main() {
             current_language_id,
             all_language_ids_names,
             set_language,
+            get_loading_screen_music_id,
+            set_loading_screen_music_id,
             i18n_value_for_key,
+            missing_translation_keys,
+            get_settings,
+            update_settings,
+            update_proxy_config,
+            reset_settings_to_default,
+            check_for_update,
             load_saved_servers,
             set_saved_server_nickname,
             set_saved_server_udp_endpoint,
             set_saved_server_https_endpoint,
+            set_saved_server_mirror_https_endpoints,
+            set_saved_server_guid,
+            set_saved_server_ticket,
+            set_saved_server_headers,
+            set_saved_server_launch_overrides,
+            set_saved_server_accept_invalid_certs,
+            open_server_website,
             add_saved_server,
             remove_saved_server,
+            repair_saved_servers,
             reorder_saved_servers,
             add_client,
+            add_client_from_folder,
+            verify_client_integrity,
             list_clients,
+            reorder_clients,
+            remove_client,
+            set_client_label,
+            set_client_layers,
+            last_used_server,
+            resolve_asset,
+            find_asset_by_crc,
+            profile_asset_map,
+            check_packs,
+            tiny_asset_report,
+            is_client_running,
+            test_proxy_connectivity,
+            active_proxy_port,
+            last_session_ticket,
+            ping_all_saved_servers,
+            remote_asset_info,
+            clear_active_client,
+            refresh_active_client_file,
+            list_orphaned_active_files,
+            delete_orphaned_active_files,
+            set_active_client_dir,
+            preview_client_config,
+            preview_copy_plan,
+            diff_client_config,
+            storage_usage,
+            remote_cache_size,
+            clear_remote_cache,
+            tail_logs,
+            prefetch_assets,
+            complete_client_install,
+            client_resource_usage,
+            last_session_remote_assets,
+            cancel_launch,
             start_client
         ])
         .run(tauri::generate_context!())
         .expect("Error while running Tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+    use std::sync::atomic::AtomicU32;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_merge_user_options_adds_missing_keys() {
+        let dir = tempdir().unwrap();
+        let template_path = dir.path().join("template.ini");
+        let user_options_path = dir.path().join(USER_OPTIONS_PATH);
+        std::fs::write(
+            &template_path,
+            "[Rendering]\nQuality=2\nShadows=1\n[Sound]\nVolume=50\n",
+        )
+        .unwrap();
+        std::fs::write(&user_options_path, "[Rendering]\nQuality=4\n").unwrap();
+
+        merge_user_options(&template_path, &user_options_path).unwrap();
+
+        let user_options = Ini::load_from_file(&user_options_path).unwrap();
+        assert_eq!(
+            user_options.get_from(Some("Rendering"), "Quality"),
+            Some("4")
+        );
+        assert_eq!(
+            user_options.get_from(Some("Rendering"), "Shadows"),
+            Some("1")
+        );
+        assert_eq!(user_options.get_from(Some("Sound"), "Volume"), Some("50"));
+    }
+
+    #[test]
+    fn test_merge_user_options_keeps_complete_file() {
+        let dir = tempdir().unwrap();
+        let template_path = dir.path().join("template.ini");
+        let user_options_path = dir.path().join(USER_OPTIONS_PATH);
+        std::fs::write(&template_path, "[Rendering]\nQuality=2\n").unwrap();
+        std::fs::write(&user_options_path, "; Custom\n[Rendering]\nQuality=4\n").unwrap();
+
+        merge_user_options(&template_path, &user_options_path).unwrap();
+
+        // Nothing was missing, so the file isn't rewritten
+        assert_eq!(
+            std::fs::read_to_string(&user_options_path).unwrap(),
+            "; Custom\n[Rendering]\nQuality=4\n"
+        );
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("test.log");
+        let log: String = (0..20_000).map(|line| format!("line {}\n", line)).collect();
+        std::fs::write(&log_path, log).unwrap();
+
+        assert_eq!(
+            tail_lines(&log_path, 3).unwrap(),
+            vec!["line 19997", "line 19998", "line 19999"]
+        );
+        assert_eq!(tail_lines(&log_path, 20_000).unwrap().len(), 20_000);
+        assert_eq!(tail_lines(&log_path, 0).unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_tail_lines_short_file() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join("test.log");
+        std::fs::write(&log_path, "first\nsecond").unwrap();
+
+        assert_eq!(tail_lines(&log_path, 10).unwrap(), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_overlapping_client_paths() {
+        let dir = tempdir().unwrap();
+        let active_client_path = dir.path().join("active_client");
+        create_dir_all(active_client_path.join("Resources")).unwrap();
+        let client_path = active_client_path.join("CloneWars.exe");
+        std::fs::write(&client_path, b"client").unwrap();
+
+        let same_client_path = active_client_path
+            .join("Resources")
+            .join("..")
+            .join("CloneWars.exe");
+        assert!(is_same_file(&client_path, &same_client_path));
+        assert!(is_inside_folder(&client_path, &active_client_path));
+        assert!(!is_inside_folder(&active_client_path, &client_path));
+
+        // A file copied onto itself needs no space and is skipped rather than truncated
+        assert_eq!(
+            required_copy_space([(client_path.clone(), same_client_path)].into_iter()),
+            0
+        );
+    }
+
+    fn test_launch_args() -> LaunchArgs {
+        LaunchArgs {
+            server: "127.0.0.1:20260".to_string(),
+            guid: DEFAULT_GUID.to_string(),
+            ticket: DEFAULT_TICKET.to_string(),
+            locale: DEFAULT_LOCALE,
+            loading_screen_id: DEFAULT_LOADING_SCREEN_ID,
+            live_gamer: true,
+            extra: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_client_command_passes_env() {
+        let client_env = HashMap::from([
+            ("DXVK_HUD".to_string(), "fps".to_string()),
+            ("LANG".to_string(), "en_US.UTF-8".to_string()),
+        ]);
+        let command = client_command(
+            Path::new("CloneWars.exe"),
+            Path::new("active_client"),
+            &client_env,
+            &test_launch_args(),
+        );
+
+        let envs: HashMap<&OsStr, Option<&OsStr>> = command.get_envs().collect();
+        assert_eq!(envs.len(), 2);
+        assert_eq!(envs[OsStr::new("DXVK_HUD")], Some(OsStr::new("fps")));
+        assert_eq!(envs[OsStr::new("LANG")], Some(OsStr::new("en_US.UTF-8")));
+        assert_eq!(command.get_current_dir(), Some(Path::new("active_client")));
+    }
+
+    #[test]
+    fn test_validate_client_env() {
+        let valid = HashMap::from([("DXVK_HUD".to_string(), "fps".to_string())]);
+        assert!(validate_client_env(&valid).is_ok());
+
+        for (name, value) in [("", "1"), ("A=B", "1"), ("A\0", "1"), ("A", "1\0")] {
+            let client_env = HashMap::from([(name.to_string(), value.to_string())]);
+            assert!(validate_client_env(&client_env).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn test_load_languages_falls_back_when_missing() {
+        let dir = tempdir().unwrap();
+        let languages = load_languages(Some(dir.path().join("i18n.json")));
+        assert!(languages.contains_key(DEFAULT_LANGUAGE_ID));
+    }
+
+    #[test]
+    fn test_load_languages_falls_back_when_corrupt() {
+        let dir = tempdir().unwrap();
+        let languages_path = dir.path().join("i18n.json");
+        std::fs::write(&languages_path, "{ not json").unwrap();
+        let languages = load_languages(Some(languages_path));
+        assert!(languages.contains_key(DEFAULT_LANGUAGE_ID));
+    }
+
+    fn test_saved_server(extra_fields: &str) -> SavedServer {
+        serde_json::from_str(&format!(
+            r#"{{"nickname": "Test", "udp_endpoint": "127.0.0.1:20260", "https_endpoint": "http://127.0.0.1:8080"{}}}"#,
+            extra_fields
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_launch_args_use_configured_guid_and_ticket() {
+        let saved_server = test_saved_server(r#", "guid": "42", "ticket": "abc123""#);
+        let args = LaunchArgs::new(
+            &saved_server,
+            session_ticket(&Settings::default(), &saved_server).unwrap(),
+        )
+        .render();
+        assert!(args.contains(&"Guid=42".to_string()));
+        assert!(args.contains(&"Ticket=abc123".to_string()));
+    }
+
+    #[test]
+    fn test_launch_args_default_guid_and_ticket() {
+        let saved_server = test_saved_server("");
+        let args = LaunchArgs::new(
+            &saved_server,
+            session_ticket(&Settings::default(), &saved_server).unwrap(),
+        )
+        .render();
+        assert!(args.contains(&format!("Guid={}", DEFAULT_GUID)));
+        assert!(args.contains(&format!("Ticket={}", DEFAULT_TICKET)));
+    }
+
+    #[test]
+    fn test_move_active_client_dir_back_to_default() {
+        let dir = tempdir().unwrap();
+        let default_path = dir.path().join("active_client");
+        let custom_path = dir.path().join("custom");
+        std::fs::create_dir_all(&custom_path).unwrap();
+        std::fs::write(custom_path.join("CloneWars.exe"), b"client").unwrap();
+
+        let mut active_client_path = custom_path.clone();
+        move_active_client_dir(&mut active_client_path, None, &default_path).unwrap();
+        assert_eq!(active_client_path, default_path);
+        assert!(default_path.is_dir());
+        assert!(!custom_path.exists());
+    }
+
+    #[test]
+    fn test_move_active_client_dir_rejects_non_empty_folder() {
+        let dir = tempdir().unwrap();
+        let default_path = dir.path().join("active_client");
+        let custom_path = dir.path().join("custom");
+        std::fs::create_dir_all(&custom_path).unwrap();
+        std::fs::write(custom_path.join("notes.txt"), b"keep me").unwrap();
+
+        let mut active_client_path = default_path.clone();
+        assert!(
+            move_active_client_dir(&mut active_client_path, Some(&custom_path), &default_path)
+                .is_err()
+        );
+        assert_eq!(active_client_path, default_path);
+        assert!(custom_path.join("notes.txt").exists());
+    }
+
+    #[test]
+    fn test_required_copy_space_counts_missing_bytes() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("source.pack");
+        let partial = dir.path().join("partial.pack");
+        let missing = dir.path().join("missing.pack");
+        std::fs::write(&source, [0; 100]).unwrap();
+        std::fs::write(&partial, [0; 40]).unwrap();
+
+        assert_eq!(
+            required_copy_space([(source.clone(), partial), (source, missing)].into_iter()),
+            160
+        );
+    }
+
+    #[test]
+    fn test_check_copy_space() {
+        assert!(check_copy_space(100, Some(100)).is_ok());
+        assert!(check_copy_space(100, None).is_ok());
+        let err = check_copy_space(101, Some(100)).unwrap_err();
+        assert!(err.starts_with("Insufficient disk space"));
+    }
+
+    async fn fake_proxy(is_alive: bool) {
+        if is_alive {
+            std::future::pending::<()>().await;
+        }
+    }
+
+    async fn supervise_fake_proxy(restarted_proxy_is_alive: bool) -> (u32, Option<u16>) {
+        let starts = Arc::new(AtomicU32::new(0));
+        let active_proxy_port = Arc::new(Mutex::new(None));
+        let run_prepare_proxy = {
+            let starts = Arc::clone(&starts);
+            move || {
+                starts.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let (ready, proxy_ready) = oneshot::channel();
+                    ready.send(4002).unwrap();
+                    Ok((fake_proxy(restarted_proxy_is_alive), proxy_ready))
+                }
+            }
+        };
+
+        let supervisor = spawn(supervise_proxy(
+            fake_proxy(false),
+            run_prepare_proxy,
+            Arc::new(Mutex::new(Some(1))),
+            Arc::clone(&active_proxy_port),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(Mutex::new(None)),
+            None,
+        ));
+
+        // A supervisor that keeps a healthy proxy running never finishes on its own
+        let _ = timeout(Duration::from_secs(60), supervisor).await;
+        let active_proxy_port = *active_proxy_port.lock().unwrap();
+        (starts.load(Ordering::SeqCst), active_proxy_port)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_supervisor_restarts_stopped_proxy() {
+        assert_eq!(supervise_fake_proxy(true).await, (1, Some(4002)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_supervisor_limits_restarts() {
+        assert_eq!(
+            supervise_fake_proxy(false).await,
+            (MAX_PROXY_RESTARTS, Some(4002))
+        );
+    }
+
+    #[test]
+    fn test_read_client_file_rejections() {
+        let dir = tempdir().unwrap();
+        let err = read_client_file(dir.path()).unwrap_err();
+        assert!(err.starts_with("The selected client is not a file"));
+
+        let err = read_client_file(&dir.path().join("missing.exe")).unwrap_err();
+        assert!(err.starts_with("The selected client does not exist"));
+
+        let empty_path = dir.path().join("empty.exe");
+        std::fs::write(&empty_path, []).unwrap();
+        let err = read_client_file(&empty_path).unwrap_err();
+        assert!(err.starts_with("The selected client is empty"));
+
+        // A sparse file is enough to exceed the cap without writing hundreds of megabytes
+        let large_path = dir.path().join("large.exe");
+        std::fs::File::create(&large_path)
+            .unwrap()
+            .set_len(MAX_CLIENT_FILE_SIZE + 1)
+            .unwrap();
+        let err = read_client_file(&large_path).unwrap_err();
+        assert!(err.starts_with("The selected client is too large"));
+
+        let client_path = dir.path().join("CloneWars.exe");
+        std::fs::write(&client_path, b"client").unwrap();
+        assert_eq!(read_client_file(&client_path).unwrap(), b"client");
+    }
+
+    #[test]
+    fn test_client_file_error_permission_denied() {
+        // Tests may run with permissions that can read any file, so check the mapping directly
+        let err = client_file_error(
+            Path::new("CloneWars.exe"),
+            io::Error::from(ErrorKind::PermissionDenied),
+        );
+        assert!(err.starts_with("Permission denied"));
+    }
+
+    async fn prefetch_server(data: &'static [u8]) -> Url {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let router = axum::Router::new().route(
+            "/assets/*asset",
+            axum::routing::get(move || async move { data }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_asset_uses_proxy_cache_key() {
+        let data = b"prefetched texture";
+        let asset_path = format!("texture.dds_{}", crc32fast::hash(data));
+        let cache_dir = tempdir().unwrap();
+        let config = proxy_config(&Settings::default(), &load_languages(None));
+        let server_url = prefetch_server(data).await;
+
+        let size = prefetch_asset(
+            &asset_path,
+            &Client::new(),
+            &[server_url],
+            &config,
+            cache_dir.path(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(size, data.len() as u64);
+
+        let cache_file =
+            http_proxy::remote_cache_file(cache_dir.path(), Path::new(&asset_path)).unwrap();
+        assert_eq!(std::fs::read(cache_file).unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_prefetch_asset_rejects_path_without_crc() {
+        let cache_dir = tempdir().unwrap();
+        let config = proxy_config(&Settings::default(), &load_languages(None));
+        let server_url = prefetch_server(b"news").await;
+
+        let result = prefetch_asset(
+            "news.txt",
+            &Client::new(),
+            &[server_url],
+            &config,
+            cache_dir.path(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(!cache_dir.path().join("news.txt").exists());
+    }
+
+    // Builds a version info node, whose length includes its header, key, value and children
+    fn version_node(
+        key: &str,
+        value_type: u16,
+        value: &[u16],
+        value_length: u16,
+        children: &[Vec<u16>],
+    ) -> Vec<u16> {
+        let mut node = vec![0, value_length, value_type];
+        node.extend(key.encode_utf16().chain([0]));
+        node.resize(node.len().next_multiple_of(2), 0);
+        node.extend_from_slice(value);
+        node.resize(node.len().next_multiple_of(2), 0);
+        node.extend(children.concat());
+        node[0] = (node.len() * 2) as u16;
+        node
+    }
+
+    fn version_string(key: &str, value: &str) -> Vec<u16> {
+        let value: Vec<u16> = value.encode_utf16().chain([0]).collect();
+        version_node(key, 1, &value, value.len() as u16, &[])
+    }
+
+    // A 32-bit executable with no code whose only section holds a version resource
+    fn pe_with_version_info(strings: &[(&str, &str)]) -> Vec<u8> {
+        const SECTION_RVA: u32 = 0x1000;
+        const HEADERS_SIZE: usize = 0x200;
+        fn put(bytes: &mut [u8], offset: usize, data: &[u8]) {
+            bytes[offset..offset + data.len()].copy_from_slice(data);
+        }
+
+        let fixed_info: Vec<u16> = [
+            0xfeef04bd, 0x10000, 0, 0, 0, 0, 0x3f, 0, 0x40004, 1, 0, 0, 0,
+        ]
+        .iter()
+        .flat_map(|&field: &u32| [field as u16, (field >> 16) as u16])
+        .collect();
+        let strings: Vec<Vec<u16>> = strings
+            .iter()
+            .map(|(key, value)| version_string(key, value))
+            .collect();
+        let string_table = version_node("040904b0", 1, &[], 0, &strings);
+        let translation = version_node("Translation", 0, &[0x0409, 0x04b0], 4, &[]);
+        let version_info = version_node(
+            "VS_VERSION_INFO",
+            0,
+            &fixed_info,
+            52,
+            &[
+                version_node("StringFileInfo", 1, &[], 0, &[string_table]),
+                version_node("VarFileInfo", 1, &[], 0, &[translation]),
+            ],
+        );
+
+        // Type, name and language directories lead to the version info's data entry
+        let mut section = Vec::new();
+        for (id, offset) in [(16, 0x8000_0018u32), (1, 0x8000_0030), (0x409, 0x48)] {
+            section.extend_from_slice(&[0; 14]);
+            section.extend_from_slice(&1u16.to_le_bytes());
+            section.extend_from_slice(&(id as u32).to_le_bytes());
+            section.extend_from_slice(&offset.to_le_bytes());
+        }
+        section.extend_from_slice(&(SECTION_RVA + 0x58).to_le_bytes());
+        section.extend_from_slice(&(version_info.len() as u32 * 2).to_le_bytes());
+        section.extend_from_slice(&[0; 8]);
+        section.extend(version_info.iter().flat_map(|word| word.to_le_bytes()));
+        let section_len = section.len() as u32;
+        section.resize(section.len().next_multiple_of(HEADERS_SIZE), 0);
+
+        let mut pe = vec![0; HEADERS_SIZE];
+        put(&mut pe, 0, b"MZ");
+        put(&mut pe, 0x3c, &0x40u32.to_le_bytes());
+        put(&mut pe, 0x40, b"PE\0\0");
+        put(&mut pe, 0x44, &0x14cu16.to_le_bytes());
+        put(&mut pe, 0x46, &1u16.to_le_bytes());
+        put(&mut pe, 0x54, &0xe0u16.to_le_bytes());
+        put(&mut pe, 0x56, &0x102u16.to_le_bytes());
+
+        let optional_header = 0x58;
+        put(&mut pe, optional_header, &0x10bu16.to_le_bytes());
+        put(&mut pe, optional_header + 28, &0x400000u32.to_le_bytes());
+        put(&mut pe, optional_header + 32, &0x1000u32.to_le_bytes());
+        put(
+            &mut pe,
+            optional_header + 36,
+            &(HEADERS_SIZE as u32).to_le_bytes(),
+        );
+        put(&mut pe, optional_header + 56, &0x2000u32.to_le_bytes());
+        put(
+            &mut pe,
+            optional_header + 60,
+            &(HEADERS_SIZE as u32).to_le_bytes(),
+        );
+        put(&mut pe, optional_header + 68, &2u16.to_le_bytes());
+        put(&mut pe, optional_header + 92, &16u32.to_le_bytes());
+        put(&mut pe, optional_header + 112, &SECTION_RVA.to_le_bytes());
+        put(&mut pe, optional_header + 116, &section_len.to_le_bytes());
+
+        let section_header = optional_header + 0xe0;
+        put(&mut pe, section_header, b".rsrc");
+        put(&mut pe, section_header + 8, &section_len.to_le_bytes());
+        put(&mut pe, section_header + 12, &SECTION_RVA.to_le_bytes());
+        put(
+            &mut pe,
+            section_header + 16,
+            &(section.len() as u32).to_le_bytes(),
+        );
+        put(
+            &mut pe,
+            section_header + 20,
+            &(HEADERS_SIZE as u32).to_le_bytes(),
+        );
+        put(&mut pe, section_header + 36, &0x40000040u32.to_le_bytes());
+
+        pe.extend(section);
+        pe
+    }
+
+    #[test]
+    fn test_detect_client_version_from_pe_version_info() {
+        let client_bytes = pe_with_version_info(&[
+            ("ProductName", "Clone Wars Adventures"),
+            ("ProductVersion", "0, 180, 1, 530619"),
+        ]);
+        assert_eq!(
+            detect_client_version(&client_bytes),
+            Some("0.180.1.530619".to_string())
+        );
+    }
+
+    #[test]
+    fn test_detect_client_version_ignores_other_executables() {
+        let client_bytes = pe_with_version_info(&[
+            ("ProductName", "Notepad"),
+            ("ProductVersion", "10.0.19041.1"),
+        ]);
+        assert_eq!(detect_client_version(&client_bytes), None);
+    }
+
+    #[test]
+    fn test_detect_client_version_prefers_embedded_string() {
+        let mut client_bytes = pe_with_version_info(&[
+            ("ProductName", "Clone Wars Adventures"),
+            ("ProductVersion", "1.0.0.1"),
+        ]);
+        client_bytes.extend_from_slice(b"0.180.1.530619");
+        assert_eq!(
+            detect_client_version(&client_bytes),
+            Some("0.180.1.530619".to_string())
+        );
+    }
+
+    #[test]
+    fn test_launch_guard_allows_one_launch() {
+        let launch_in_progress = AtomicBool::new(false);
+        let barrier = std::sync::Barrier::new(8);
+        let launches = AtomicU32::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    barrier.wait();
+                    let launch_guard = LaunchGuard::acquire(&launch_in_progress);
+                    if launch_guard.is_ok() {
+                        launches.fetch_add(1, Ordering::SeqCst);
+                    }
+
+                    // Hold the guard until every other launch has tried to start
+                    barrier.wait();
+                });
+            }
+        });
+        assert_eq!(launches.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_launch_guard_released_on_drop() {
+        let launch_in_progress = AtomicBool::new(false);
+        let launch_guard = LaunchGuard::acquire(&launch_in_progress).unwrap();
+        assert_eq!(
+            LaunchGuard::acquire(&launch_in_progress).err(),
+            Some("Launch already in progress".to_string())
+        );
+        drop(launch_guard);
+        assert!(LaunchGuard::acquire(&launch_in_progress).is_ok());
+    }
+
+    #[test]
+    fn test_update_live_proxy_config() {
+        let languages = load_languages(None);
+        let mut config = proxy_config(&Settings::default(), &languages);
+        let settings = Settings {
+            proxy_always_remote_patterns: vec!["*.txt".to_string()],
+            proxy_strip_name_hash: false,
+            ..Settings::default()
+        };
+
+        update_live_proxy_config(&mut config, proxy_config(&settings, &languages));
+        assert!(config.always_remote.unwrap().is_match("news.txt"));
+        assert!(!config.strip_name_hash);
+    }
+
+    #[test]
+    fn test_app_error_serializes_code_and_message() {
+        assert_eq!(
+            serde_json::to_value(AppError::GameAlreadyRunning).unwrap(),
+            serde_json::json!({
+                "code": "game_already_running",
+                "message": "Game is already running",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::bad_endpoint(
+                "not a url",
+                "relative URL without a base"
+            ))
+            .unwrap(),
+            serde_json::json!({
+                "code": "bad_endpoint",
+                "message": "Bad HTTPS endpoint not a url: relative URL without a base",
+            })
+        );
+        assert_eq!(
+            serde_json::to_value(AppError::from("Launch already in progress")).unwrap(),
+            serde_json::json!({
+                "code": "other",
+                "message": "Launch already in progress",
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_header_map() {
+        let headers = server_header_map(&HashMap::from([(
+            "X-Api-Key".to_string(),
+            "secret".to_string(),
+        )]))
+        .unwrap();
+        assert_eq!(headers["x-api-key"], "secret");
+        assert!(headers["x-api-key"].is_sensitive());
+        assert!(!format!("{:?}", headers).contains("secret"));
+
+        assert!(
+            server_header_map(&HashMap::from([("Bad Name".to_string(), String::new())])).is_err()
+        );
+        assert!(server_header_map(&HashMap::from([(
+            "X-Api-Key".to_string(),
+            "line\nbreak".to_string()
+        )]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_apply_missing_user_options_template() {
+        let dir = tempdir().unwrap();
+        let user_options_path = dir.path().join(USER_OPTIONS_PATH);
+        apply_user_options_template(None, &user_options_path).unwrap();
+        assert!(!user_options_path.exists());
+
+        std::fs::write(&user_options_path, "[Sound]\nVolume=20\n").unwrap();
+        apply_user_options_template(None, &user_options_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&user_options_path).unwrap(),
+            "[Sound]\nVolume=20\n"
+        );
+    }
+
+    #[test]
+    fn test_apply_user_options_template_copies_template() {
+        let dir = tempdir().unwrap();
+        let template_path = dir.path().join("template.ini");
+        let user_options_path = dir.path().join(USER_OPTIONS_PATH);
+        std::fs::write(&template_path, "[Sound]\nVolume=50\n").unwrap();
+
+        apply_user_options_template(Some(&template_path), &user_options_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&user_options_path).unwrap(),
+            "[Sound]\nVolume=50\n"
+        );
+    }
+
+    #[test]
+    fn test_random_tickets_differ_between_launches() {
+        let settings = Settings {
+            generate_random_ticket: true,
+            ..Settings::default()
+        };
+        let saved_server = test_saved_server("");
+        let first_ticket = session_ticket(&settings, &saved_server).unwrap();
+        let second_ticket = session_ticket(&settings, &saved_server).unwrap();
+        assert_eq!(first_ticket.chars().count(), settings.random_ticket_length);
+        assert_ne!(first_ticket, second_ticket);
+        assert_ne!(first_ticket, DEFAULT_TICKET);
+    }
+
+    #[test]
+    fn test_random_ticket_empty_charset() {
+        assert!(random_ticket(15, "").is_err());
+    }
+
+    #[test]
+    fn test_asset_server_url() {
+        let saved_server = test_saved_server("");
+        let mut settings = Settings {
+            proxy_port: 4001,
+            ..Settings::default()
+        };
+        assert_eq!(
+            asset_server_url(&settings, &saved_server),
+            "http://127.0.0.1:4001"
+        );
+
+        settings.direct_connect = true;
+        assert_eq!(
+            asset_server_url(&settings, &saved_server),
+            "http://127.0.0.1:8080"
+        );
+    }
+
+    #[test]
+    fn test_launch_args_render_order() {
+        let launch_args = LaunchArgs {
+            server: "play.example.com:20260".to_string(),
+            guid: "7".to_string(),
+            ticket: "abc".to_string(),
+            locale: 9,
+            loading_screen_id: 3,
+            live_gamer: false,
+            extra: vec!["Debug=1".to_string(), "Windowed=1".to_string()],
+        };
+        assert_eq!(
+            launch_args.render(),
+            vec![
+                format!("inifile={}", CLIENT_CONFIG_PATH),
+                "Guid=7".to_string(),
+                "Server=play.example.com:20260".to_string(),
+                "Ticket=abc".to_string(),
+                "Internationalization:Locale=9".to_string(),
+                "LoadingScreenId=3".to_string(),
+                "LiveGamer=0".to_string(),
+                "Debug=1".to_string(),
+                "Windowed=1".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_launch_args_use_server_overrides() {
+        let saved_server = test_saved_server(
+            r#", "launch_overrides": {"locale": 9, "live_gamer": false, "extra": ["Debug=1"]}"#,
+        );
+        let launch_args = LaunchArgs::new(&saved_server, "abc".to_string());
+        assert_eq!(launch_args.locale, 9);
+        assert_eq!(launch_args.loading_screen_id, DEFAULT_LOADING_SCREEN_ID);
+        assert!(!launch_args.live_gamer);
+        assert_eq!(launch_args.extra, vec!["Debug=1".to_string()]);
+    }
+
+    #[test]
+    fn test_bad_saved_servers_backed_up_before_save() {
+        let dir = tempdir().unwrap();
+        let saved_servers_path = dir.path().join(SAVED_SERVERS_PATH);
+        let bad_file = r#"[{"nickname": "Good", "udp_endpoint": "127.0.0.1:20260", "https_endpoint": "http://127.0.0.1:8080"}, {"nickname": 5}]"#;
+        std::fs::write(&saved_servers_path, bad_file).unwrap();
+
+        let saved_servers = load_saved_servers_file(&saved_servers_path);
+        assert_eq!(saved_servers.len(), 1);
+        assert_eq!(saved_servers[0].nickname, "Good");
+
+        // Saving the loaded list must not lose the entry that was skipped
+        save_server_list(&saved_servers, &saved_servers_path).unwrap();
+        let backup_path = saved_servers_path.with_extension(SAVED_SERVERS_BACKUP_EXTENSION);
+        assert_eq!(std::fs::read_to_string(backup_path).unwrap(), bad_file);
+    }
+
+    #[test]
+    fn test_valid_saved_servers_not_backed_up() {
+        let dir = tempdir().unwrap();
+        let saved_servers_path = dir.path().join(SAVED_SERVERS_PATH);
+        std::fs::write(&saved_servers_path, "[]").unwrap();
+
+        assert!(load_saved_servers_file(&saved_servers_path).is_empty());
+        assert!(!saved_servers_path
+            .with_extension(SAVED_SERVERS_BACKUP_EXTENSION)
+            .exists());
+    }
+}
@@ -25,8 +25,9 @@ async function try_or_show_err_dialog(promise, i18n_key) {
     return await promise
   } catch (err) {
     console.error('Unable to write saved servers:', err)
+    // Some commands return structured errors with a code and message
     message(
-      `${await getI18nValueForKey(i18n_key)}\n${err}`,
+      `${await getI18nValueForKey(i18n_key)}\n${err?.message ?? err}`,
       {
         okLabel: await getI18nValueForKey('ok'),
         type: 'error'
@@ -348,7 +349,7 @@ async function initAddClientButton(buttonElement, listElement) {
 
     if (clientPath) {
       const addClient = async () => {
-        const clientVersion = await invoke('add_client', {path: clientPath})
+        const clientVersion = await invoke('add_client', {path: clientPath, overwrite: false})
         await refreshClientList(listElement)
         message(
           `${await getI18nValueForKey('settings-added-client')}\n${clientVersion}`,
@@ -367,17 +368,26 @@ async function initAddClientButton(buttonElement, listElement) {
   })
 }
 
+async function reorderClients(oldIndex, newIndex) {
+  await try_or_show_err_dialog(invoke('reorder_clients', { oldIndex, newIndex }), SETTINGS_WRITE_FAILED_I18N_KEY)
+}
+
 async function refreshClientList(element) {
   while (element.lastElementChild) {
     element.removeChild(element.lastElementChild)
   }
 
   const clientList = await invoke('list_clients')
-  clientList.sort(([version1, ], [version2, ]) => version1.localeCompare(version2))
 
-  for (const [clientVersion, clientPath] of clientList) {
+  for (const [clientVersion, clientLabel, clientPath, clientAvailable] of clientList) {
     const listItem = document.createElement('li')
-    listItem.textContent = `${clientVersion} (${clientPath})`
+    listItem.draggable = true
+    listItem.textContent = clientLabel
+      ? `${clientLabel} - ${clientVersion} (${clientPath})`
+      : `${clientVersion} (${clientPath})`
+    if (!clientAvailable) {
+      listItem.textContent += ` - ${await getI18nValueForKey('settings-client-unavailable')}`
+    }
     element.append(listItem)
   }
 
@@ -396,6 +406,7 @@ async function main() {
   })
 
   const clientList = document.getElementById('client-list')
+  initDraggableList(clientList, reorderClients)
   await initAddClientButton(document.getElementById('add-client-btn'), clientList)
 
   if (await refreshClientList(clientList) === 0) {